@@ -0,0 +1,265 @@
+use std::time::{Duration, Instant};
+
+use tetra::graphics::text::{Font, Text};
+use tetra::graphics::{Color, DrawParams};
+use tetra::input;
+use tetra::math::Vec2;
+use tetra::{Context, Event};
+
+use crate::bindings::{Action, Bindings};
+use crate::scene::Scene;
+
+const LIST_LEFT: f32 = 10.0;
+/// Top of the "Animations" heading, below the rest of the HUD.
+const LIST_TOP: f32 = 150.0;
+const LIST_WIDTH: f32 = 480.0;
+const ROW_HEIGHT: f32 = 20.0;
+/// Space kept free below the list for messages.
+const LIST_BOTTOM_MARGIN: f32 = 90.0;
+/// Digits typed within this long of each other form one animation number.
+const NUMBER_TIMEOUT: Duration = Duration::from_millis(800);
+
+const CURRENT_COLOR: Color = Color::rgb(1.0, 0.85, 0.2);
+
+/// The HUD list of animations: scrolls, selects on click, freezes from its checkboxes and
+/// filters by name.
+#[derive(Default)]
+pub struct AnimationList {
+    /// First visible row.
+    scroll: usize,
+    /// Only animations whose name contains this, ignoring case, are listed.
+    filter: String,
+    /// Whether typed text goes to `filter`.
+    editing_filter: bool,
+    /// Animation number being typed and when its last digit was.
+    number: Option<(usize, Instant)>,
+    /// Measured width of a checkbox, set on first draw.
+    checkbox_width: f32,
+}
+
+impl AnimationList {
+    /// Indices of the animations that pass the filter.
+    pub fn visible(&self, scene: &Scene) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        scene
+            .bsv3
+            .animations
+            .iter()
+            .enumerate()
+            .filter(|(_, animation)| animation.name.to_lowercase().contains(&filter))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn row_count(view_size: Vec2<f32>) -> usize {
+        ((view_size.y - LIST_TOP - ROW_HEIGHT - LIST_BOTTOM_MARGIN) / ROW_HEIGHT).max(1.0) as usize
+    }
+
+    /// Whether `position` on screen is over the list, where the wheel scrolls instead of zooming.
+    pub fn contains(&self, position: Vec2<f32>, view_size: Vec2<f32>) -> bool {
+        let height = ROW_HEIGHT * (Self::row_count(view_size) + 1) as f32;
+        position.x >= LIST_LEFT
+            && position.x < LIST_LEFT + LIST_WIDTH
+            && position.y >= LIST_TOP
+            && position.y < LIST_TOP + height
+    }
+
+    pub fn scroll_by(&mut self, rows: i32, scene: &Scene, view_size: Vec2<f32>) {
+        let max_scroll = self
+            .visible(scene)
+            .len()
+            .saturating_sub(Self::row_count(view_size));
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(rows as isize)
+            .min(max_scroll);
+    }
+
+    /// Scrolls back to the top, for a newly opened file.
+    pub fn reset_scroll(&mut self) {
+        self.scroll = 0;
+    }
+
+    /// Switches to `animation`, scrolling it into view.
+    fn select(&mut self, scene: &mut Scene, animation: usize, view_size: Vec2<f32>) {
+        scene.animation = animation;
+        scene.timer = 0;
+
+        if let Some(row) = self
+            .visible(scene)
+            .iter()
+            .position(|&index| index == animation)
+        {
+            let rows = Self::row_count(view_size);
+            if row < self.scroll {
+                self.scroll = row;
+            } else if row >= self.scroll + rows {
+                self.scroll = row + 1 - rows;
+            }
+        }
+    }
+
+    /// Handles clicks and filter typing, returning whether the event was consumed. Keys only
+    /// edit the filter while it is typed, through [`Action::DeleteChar`] and
+    /// [`Action::FinishTyping`].
+    pub fn event(
+        &mut self,
+        ctx: &Context,
+        event: &Event,
+        scene: &mut Scene,
+        bindings: &Bindings,
+        view_size: Vec2<f32>,
+    ) -> bool {
+        if self.editing_filter {
+            match event {
+                Event::TextInput { text } => self.filter.push_str(text),
+                Event::KeyPressed { key } => {
+                    let shift = input::is_key_modifier_down(ctx, input::KeyModifier::Shift);
+                    match bindings.key_action(*key, shift) {
+                        Some(Action::DeleteChar) => {
+                            self.filter.pop();
+                        }
+                        Some(Action::FinishTyping) => self.editing_filter = false,
+                        _ => {}
+                    }
+                }
+                Event::KeyReleased { .. } => {}
+                _ => return false,
+            }
+            self.scroll = 0;
+            return true;
+        }
+
+        match event {
+            Event::MouseButtonPressed { button } if bindings.is_click(*button) => {
+                let position = input::get_mouse_position(ctx);
+                if !self.contains(position, view_size) {
+                    return false;
+                }
+
+                // Row 0 is the heading
+                let row = ((position.y - LIST_TOP) / ROW_HEIGHT) as usize;
+                let visible = self.visible(scene);
+                let Some(&animation) = row
+                    .checked_sub(1)
+                    .and_then(|row| visible.get(self.scroll + row))
+                else {
+                    return true;
+                };
+
+                if position.x < LIST_LEFT + self.checkbox_width {
+                    scene.toggle_frozen(animation);
+                } else {
+                    self.select(scene, animation, view_size);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Adds `digit` to the animation number being typed and selects that animation.
+    pub fn type_digit(&mut self, digit: u8, scene: &mut Scene, view_size: Vec2<f32>) {
+        let number = match self.number {
+            Some((number, typed)) if typed.elapsed() < NUMBER_TIMEOUT => {
+                number.saturating_mul(10).saturating_add(digit as usize)
+            }
+            _ => digit as usize,
+        };
+        self.number = Some((number, Instant::now()));
+        if number < scene.bsv3.animations.len() {
+            self.select(scene, number, view_size);
+        }
+    }
+
+    /// Starts typing a new name filter.
+    pub fn start_filter(&mut self) {
+        self.editing_filter = true;
+        self.filter.clear();
+    }
+
+    /// Selects the animation `rows` away from the current one in the filtered list.
+    pub fn select_by(&mut self, rows: isize, scene: &mut Scene, view_size: Vec2<f32>) {
+        let visible = self.visible(scene);
+        if visible.is_empty() {
+            return;
+        }
+
+        let row = match visible.iter().position(|&index| index == scene.animation) {
+            Some(row) => row.saturating_add_signed(rows).min(visible.len() - 1),
+            None => 0,
+        };
+        self.select(scene, visible[row], view_size);
+    }
+
+    /// Scrolls by a screenful of rows, up when `pages` is negative.
+    pub fn scroll_pages(&mut self, pages: i32, scene: &Scene, view_size: Vec2<f32>) {
+        self.scroll_by(pages * Self::row_count(view_size) as i32, scene, view_size);
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, font: &Font, scene: &Scene, view_size: Vec2<f32>) {
+        if self.checkbox_width == 0.0 {
+            self.checkbox_width = Text::new("[X]", font.clone())
+                .get_bounds(ctx)
+                .map_or(30.0, |bounds| bounds.width);
+        }
+
+        // The list may have shrunk on reload or the window with it
+        self.scroll_by(0, scene, view_size);
+
+        let visible = self.visible(scene);
+        let heading = if self.editing_filter {
+            format!("Animations /{}_", self.filter)
+        } else if !self.filter.is_empty() {
+            format!(
+                "Animations /{} ({} of {})",
+                self.filter,
+                visible.len(),
+                scene.bsv3.animations.len()
+            )
+        } else {
+            String::from("Animations (/ to filter)")
+        };
+        Text::new(heading, font.clone()).draw(ctx, Vec2::new(LIST_LEFT, LIST_TOP));
+
+        let rows = Self::row_count(view_size);
+        for (row, &index) in visible.iter().skip(self.scroll).take(rows).enumerate() {
+            let animation = &scene.bsv3.animations[index];
+            let text = format!(
+                "{} {:03} ({:03} - {:03}) {}",
+                if scene.always_draw_animations.contains(&index) {
+                    "[X]"
+                } else {
+                    "[ ]"
+                },
+                index,
+                animation.start,
+                animation.end,
+                animation.name,
+            );
+            let color = if index == scene.animation {
+                CURRENT_COLOR
+            } else {
+                Color::WHITE
+            };
+            Text::new(text, font.clone()).draw(
+                ctx,
+                DrawParams::default()
+                    .position(Vec2::new(
+                        LIST_LEFT,
+                        LIST_TOP + ROW_HEIGHT * (row + 1) as f32,
+                    ))
+                    .color(color),
+            );
+        }
+
+        let hidden = visible.len().saturating_sub(self.scroll + rows);
+        if self.scroll > 0 || hidden > 0 {
+            let text = format!("{} above, {} below", self.scroll, hidden);
+            Text::new(text, font.clone()).draw(
+                ctx,
+                Vec2::new(LIST_LEFT, LIST_TOP + ROW_HEIGHT * (rows + 1) as f32),
+            );
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use image::{Rgba, RgbaImage};
+use tetra::graphics::Color;
+
+const GREY: [u8; 3] = [128, 128, 128];
+const GREEN: [u8; 3] = [102, 187, 102];
+const BLUE: [u8; 3] = [0, 136, 204];
+
+/// Side of one checkerboard square in pixels.
+const CHECKER_SIZE: u32 = 16;
+const CHECKER_LIGHT: [u8; 3] = [204, 204, 204];
+const CHECKER_DARK: [u8; 3] = [153, 153, 153];
+
+/// What the asset is drawn over, in the viewer and in exports.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Background {
+    Color([u8; 3]),
+    /// Grey squares showing where the asset is transparent.
+    Checkerboard,
+    /// An image tiled from the top left, e.g. a grass tile.
+    Image(String),
+}
+
+impl Default for Background {
+    fn default() -> Background {
+        Background::Color(GREY)
+    }
+}
+
+impl Background {
+    /// Accepts `grey`, `green`, `blue`, `checker`, `#RRGGBB` or the path of an image.
+    pub fn parse(value: &str) -> Option<Background> {
+        let value = value.trim();
+        match value.to_lowercase().as_str() {
+            "grey" | "gray" => return Some(Background::Color(GREY)),
+            "green" => return Some(Background::Color(GREEN)),
+            "blue" => return Some(Background::Color(BLUE)),
+            "checker" | "checkerboard" => return Some(Background::Checkerboard),
+            _ => {}
+        }
+
+        if let Some(hex) = value.strip_prefix('#') {
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let color = u32::from_str_radix(hex, 16).ok()?;
+            return Some(Background::Color([
+                (color >> 16) as u8,
+                (color >> 8) as u8,
+                color as u8,
+            ]));
+        }
+
+        if std::path::Path::new(value).is_file() {
+            return Some(Background::Image(value.into()));
+        }
+
+        None
+    }
+
+    /// Cycles through the built in colours and the checkerboard.
+    pub fn next(&self) -> Background {
+        match self {
+            Background::Color(GREY) => Background::Color(GREEN),
+            Background::Color(GREEN) => Background::Color(BLUE),
+            Background::Color(BLUE) => Background::Checkerboard,
+            _ => Background::Color(GREY),
+        }
+    }
+
+    /// Colour to clear with, `None` when the background has to be drawn from [`Background::render`].
+    pub fn color(&self) -> Option<Color> {
+        match self {
+            Background::Color([r, g, b]) => Some(Color::rgb8(*r, *g, *b)),
+            _ => None,
+        }
+    }
+
+    /// The background as an opaque `width` by `height` image.
+    pub fn render(&self, width: u32, height: u32) -> image::ImageResult<RgbaImage> {
+        match self {
+            Background::Color([r, g, b]) => Ok(RgbaImage::from_pixel(
+                width,
+                height,
+                Rgba([*r, *g, *b, 255]),
+            )),
+            Background::Checkerboard => Ok(RgbaImage::from_fn(width, height, |x, y| {
+                let [r, g, b] = if (x / CHECKER_SIZE + y / CHECKER_SIZE).is_multiple_of(2) {
+                    CHECKER_LIGHT
+                } else {
+                    CHECKER_DARK
+                };
+                Rgba([r, g, b, 255])
+            })),
+            Background::Image(path) => {
+                let tile = image::open(path)?.into_rgba8();
+                Ok(RgbaImage::from_fn(width, height, |x, y| {
+                    let Rgba([r, g, b, a]) =
+                        *tile.get_pixel(x % tile.width().max(1), y % tile.height().max(1));
+                    // Flatten over black so the result stays opaque
+                    let blend = |value: u8| (value as u32 * a as u32 / 255) as u8;
+                    Rgba([blend(r), blend(g), blend(b), 255])
+                }))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Background {
+    /// Written so [`Background::parse`] reads it back.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Background::Color(GREY) => write!(f, "grey"),
+            Background::Color(GREEN) => write!(f, "green"),
+            Background::Color(BLUE) => write!(f, "blue"),
+            Background::Color([r, g, b]) => write!(f, "#{:02X}{:02X}{:02X}", r, g, b),
+            Background::Checkerboard => write!(f, "checker"),
+            Background::Image(path) => write!(f, "{}", path),
+        }
+    }
+}
+
+/// Composites a premultiplied `canvas` over an opaque `background` of the same size.
+pub fn flatten(canvas: &mut RgbaImage, background: &RgbaImage) {
+    for (pixel, below) in canvas.pixels_mut().zip(background.pixels()) {
+        let alpha = pixel[3] as u32;
+        for (channel, value) in pixel.0.iter_mut().zip(below.0) {
+            *channel = (*channel as u32 + value as u32 * (255 - alpha) / 255).min(255) as u8;
+        }
+    }
+}
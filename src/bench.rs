@@ -0,0 +1,145 @@
+use colored::Colorize;
+use tetra::graphics::{self, Canvas, Color, DrawParams};
+use tetra::math::Vec2;
+use tetra::time::Timestep;
+use tetra::{window, Context, ContextBuilder, State, TetraError};
+use tsto_bsv3::ea::bsv3::{Animation, AnimationKind, Frame};
+
+use crate::scene::Scene;
+use crate::{CANVAS_SIZE, WINDOW_HEIGHT, WINDOW_WIDTH};
+
+/// `bsv3 bench [file.bsv3] [--frames N] [--ticks N]`
+///
+/// Renders as fast as possible and reports the time per tick. With `--frames` the asset is
+/// replaced by that many generated frames, each layer with a distinct transform, to stress assets
+/// with long animations.
+pub fn run(args: &[String]) -> Result<(), TetraError> {
+    let mut file_path = String::from("./building.bsv3");
+    let mut frames = None;
+    let mut ticks = 1000;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frames" => frames = args.next().and_then(|value| value.parse().ok()),
+            "--ticks" => {
+                ticks = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(ticks)
+                    .max(1)
+            }
+            _ => file_path = arg.clone(),
+        }
+    }
+
+    ContextBuilder::new("TSTO BSV3 Bench", WINDOW_WIDTH as i32, WINDOW_HEIGHT as i32)
+        .vsync(false)
+        .timestep(Timestep::Variable)
+        .build()?
+        .run(|ctx| BenchState::new(ctx, &file_path, frames, ticks))
+}
+
+struct BenchState {
+    scene: Scene,
+    canvas: Canvas,
+    ticks: usize,
+    start_time: std::time::Instant,
+}
+
+impl BenchState {
+    fn new(
+        ctx: &mut Context,
+        file_path: &str,
+        frames: Option<usize>,
+        ticks: usize,
+    ) -> Result<BenchState, TetraError> {
+        let mut scene = Scene::new(ctx, file_path)?;
+
+        if let Some(frame_count) = frames {
+            generate_frames(&mut scene, frame_count.clamp(1, u16::MAX as usize));
+        }
+
+        println!(
+            "{} {} frames for {} ticks",
+            "Benchmarking".blue(),
+            scene.bsv3.frames.len(),
+            ticks
+        );
+
+        Ok(BenchState {
+            scene,
+            canvas: Canvas::new(ctx, CANVAS_SIZE as i32, CANVAS_SIZE as i32)?,
+            ticks,
+            start_time: std::time::Instant::now(),
+        })
+    }
+}
+
+/// Replaces the animations with one long animation whose frames all differ slightly.
+fn generate_frames(scene: &mut Scene, frame_count: usize) {
+    let bsv3 = &mut scene.bsv3;
+    if bsv3.frames.is_empty() {
+        return;
+    }
+
+    let frames = (0..frame_count)
+        .map(|index| {
+            let mut frame: Frame = bsv3.frames[index % bsv3.frames.len()].clone();
+            let angle = index as f32 * 0.01;
+            for layer in frame.layers.iter_mut() {
+                layer.scale_x = angle.cos();
+                layer.skew_h = -angle.sin();
+                layer.skew_v = angle.sin();
+                layer.scale_y = angle.cos();
+                layer.x += index as f32 * 0.1;
+            }
+            frame
+        })
+        .collect::<Vec<Frame>>();
+
+    bsv3.frames = frames;
+    bsv3.groups.clear();
+    bsv3.animations = vec![Animation {
+        name: String::from("Bench"),
+        start: 0,
+        end: (frame_count - 1) as u16,
+        kind: AnimationKind::Frames,
+    }];
+    scene.animation = 0;
+    scene.always_draw_animations.clear();
+}
+
+impl State for BenchState {
+    fn update(&mut self, ctx: &mut Context) -> tetra::Result {
+        self.scene.draw(ctx, &self.canvas, 0, 1.0, false);
+
+        if self.scene.timer >= self.ticks {
+            // Wait for the GPU to finish before stopping the clock
+            self.canvas.texture().get_data(ctx);
+
+            let elapsed = self.start_time.elapsed();
+            println!(
+                "{} {:?} total, {:?} per tick",
+                "Done in".green(),
+                elapsed,
+                elapsed / self.ticks as u32
+            );
+            window::quit(ctx);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> tetra::Result {
+        graphics::clear(ctx, Color::BLACK);
+        self.canvas.draw(
+            ctx,
+            DrawParams::default().scale(Vec2::new(
+                WINDOW_WIDTH / CANVAS_SIZE,
+                WINDOW_HEIGHT / CANVAS_SIZE,
+            )),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,725 @@
+use tetra::input::{Key, MouseButton};
+
+/// Everything the viewer does in response to a key or mouse button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Help,
+    Pan,
+    /// Picks a row of the animation list or the recent files menu. Only mouse buttons, which may
+    /// also be bound to an action used elsewhere in the window.
+    Click,
+    NextAnimation,
+    PreviousAnimation,
+    CycleAnimation,
+    FilterAnimations,
+    ScrollUp,
+    ScrollDown,
+    Freeze,
+    Fit,
+    ActualSize,
+    Background,
+    BackgroundColor,
+    NearestFilter,
+    Inspect,
+    OverlayOrigin,
+    OverlayLayers,
+    OverlayBounds,
+    OverlayGrid,
+    FewerOnionFrames,
+    MoreOnionFrames,
+    NextSprite,
+    PreviousSprite,
+    PickSprite,
+    HideSprite,
+    SoloSprite,
+    HighlightSprite,
+    ShowAllSprites,
+    Record,
+    Screenshot,
+    ScreenshotWithBackground,
+    SlowerPlayback,
+    FasterPlayback,
+    FrameRate,
+    RecentFiles,
+    NextRecentFile,
+    CompareMode,
+    CloseComparison,
+    Town,
+    RemoveBuilding,
+    Tween,
+    /// Types a digit of an animation number.
+    Digit(u8),
+    DeleteChar,
+    FinishTyping,
+}
+
+impl Action {
+    /// Every action, in the order the help lists them.
+    pub const ALL: [Action; 54] = [
+        Action::Help,
+        Action::Pan,
+        Action::Click,
+        Action::NextAnimation,
+        Action::PreviousAnimation,
+        Action::CycleAnimation,
+        Action::FilterAnimations,
+        Action::ScrollUp,
+        Action::ScrollDown,
+        Action::Freeze,
+        Action::Fit,
+        Action::ActualSize,
+        Action::Background,
+        Action::BackgroundColor,
+        Action::NearestFilter,
+        Action::Inspect,
+        Action::OverlayOrigin,
+        Action::OverlayLayers,
+        Action::OverlayBounds,
+        Action::OverlayGrid,
+        Action::FewerOnionFrames,
+        Action::MoreOnionFrames,
+        Action::NextSprite,
+        Action::PreviousSprite,
+        Action::PickSprite,
+        Action::HideSprite,
+        Action::SoloSprite,
+        Action::HighlightSprite,
+        Action::ShowAllSprites,
+        Action::Record,
+        Action::Screenshot,
+        Action::ScreenshotWithBackground,
+        Action::SlowerPlayback,
+        Action::FasterPlayback,
+        Action::FrameRate,
+        Action::RecentFiles,
+        Action::NextRecentFile,
+        Action::CompareMode,
+        Action::CloseComparison,
+        Action::Town,
+        Action::RemoveBuilding,
+        Action::Tween,
+        Action::Digit(0),
+        Action::Digit(1),
+        Action::Digit(2),
+        Action::Digit(3),
+        Action::Digit(4),
+        Action::Digit(5),
+        Action::Digit(6),
+        Action::Digit(7),
+        Action::Digit(8),
+        Action::Digit(9),
+        Action::DeleteChar,
+        Action::FinishTyping,
+    ];
+
+    /// Name used for `bind <name> = <keys>` in the settings file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Help => "help",
+            Action::Pan => "pan",
+            Action::Click => "click",
+            Action::NextAnimation => "next-animation",
+            Action::PreviousAnimation => "previous-animation",
+            Action::CycleAnimation => "cycle-animation",
+            Action::FilterAnimations => "filter-animations",
+            Action::ScrollUp => "scroll-up",
+            Action::ScrollDown => "scroll-down",
+            Action::Freeze => "freeze",
+            Action::Fit => "fit",
+            Action::ActualSize => "actual-size",
+            Action::Background => "background",
+            Action::BackgroundColor => "background-color",
+            Action::NearestFilter => "nearest-filter",
+            Action::Inspect => "inspect",
+            Action::OverlayOrigin => "overlay-origin",
+            Action::OverlayLayers => "overlay-layers",
+            Action::OverlayBounds => "overlay-bounds",
+            Action::OverlayGrid => "overlay-grid",
+            Action::FewerOnionFrames => "fewer-onion-frames",
+            Action::MoreOnionFrames => "more-onion-frames",
+            Action::NextSprite => "next-sprite",
+            Action::PreviousSprite => "previous-sprite",
+            Action::PickSprite => "pick-sprite",
+            Action::HideSprite => "hide-sprite",
+            Action::SoloSprite => "solo-sprite",
+            Action::HighlightSprite => "highlight-sprite",
+            Action::ShowAllSprites => "show-all-sprites",
+            Action::Record => "record",
+            Action::Screenshot => "screenshot",
+            Action::ScreenshotWithBackground => "screenshot-with-background",
+            Action::SlowerPlayback => "slower-playback",
+            Action::FasterPlayback => "faster-playback",
+            Action::FrameRate => "frame-rate",
+            Action::RecentFiles => "recent-files",
+            Action::NextRecentFile => "next-recent-file",
+            Action::CompareMode => "compare-mode",
+            Action::CloseComparison => "close-comparison",
+            Action::Town => "town",
+            Action::RemoveBuilding => "remove-building",
+            Action::Tween => "tween",
+            Action::Digit(digit) => DIGIT_NAMES
+                .get(*digit as usize)
+                .copied()
+                .unwrap_or_default(),
+            Action::DeleteChar => "delete-char",
+            Action::FinishTyping => "finish-typing",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Help => "Show or hide this help",
+            Action::Pan => "Drag to pan",
+            Action::Click => "Pick an animation or recent file",
+            Action::NextAnimation => "Select the next animation in the list",
+            Action::PreviousAnimation => "Select the previous animation in the list",
+            Action::CycleAnimation => "Cycle through every animation",
+            Action::FilterAnimations => "Filter the animation list by name",
+            Action::ScrollUp => "Scroll the animation list up",
+            Action::ScrollDown => "Scroll the animation list down",
+            Action::Freeze => "Freeze the current animation",
+            Action::Fit => "Fit the asset to the window",
+            Action::ActualSize => "Zoom to 1:1",
+            Action::Background => "Cycle the background",
+            Action::BackgroundColor => "Type a background colour",
+            Action::NearestFilter => "Toggle nearest-neighbour filtering",
+            Action::Inspect => "Toggle the pixel grid and colour readout",
+            Action::OverlayOrigin => "Toggle the origin overlay",
+            Action::OverlayLayers => "Toggle the layer outline overlay",
+            Action::OverlayBounds => "Toggle the bounds overlay",
+            Action::OverlayGrid => "Toggle the isometric tile grid",
+            Action::FewerOnionFrames => "Show fewer onion skin frames",
+            Action::MoreOnionFrames => "Show more onion skin frames",
+            Action::NextSprite => "Select the next sprite",
+            Action::PreviousSprite => "Select the previous sprite",
+            Action::PickSprite => "Select the sprite under the cursor",
+            Action::HideSprite => "Hide or show the selected sprite",
+            Action::SoloSprite => "Show only the selected sprite",
+            Action::HighlightSprite => "Highlight the selected sprite",
+            Action::ShowAllSprites => "Show every sprite again",
+            Action::Record => "Record one loop to PNGs",
+            Action::Screenshot => "Save a screenshot",
+            Action::ScreenshotWithBackground => "Save a screenshot with the background",
+            Action::SlowerPlayback => "Halve the playback speed",
+            Action::FasterPlayback => "Double the playback speed",
+            Action::FrameRate => "Cycle the asset's frame rate",
+            Action::RecentFiles => "Show recently opened files",
+            Action::NextRecentFile => "Open the next recent file",
+            Action::CompareMode => "Compare side by side or as a difference",
+            Action::CloseComparison => "Stop comparing",
+            Action::Town => "Place assets on a town grid, or go back",
+            Action::RemoveBuilding => "Remove the selected building from the town",
+            Action::Tween => "Tween between steps at 48 or 60 fps, or not",
+            Action::Digit(_) => "Jump to an animation by number",
+            Action::DeleteChar => "Delete the last character typed",
+            Action::FinishTyping => "Finish typing a filter or colour",
+        }
+    }
+
+    fn default_bindings(&self) -> &'static str {
+        match self {
+            Action::Help => "H, ?",
+            Action::Pan => "MouseLeft",
+            Action::Click => "MouseLeft",
+            Action::NextAnimation => "Down",
+            Action::PreviousAnimation => "Up",
+            Action::CycleAnimation => "MouseRight",
+            Action::FilterAnimations => "/",
+            Action::ScrollUp => "PageUp",
+            Action::ScrollDown => "PageDown",
+            Action::Freeze => "F",
+            Action::Fit => "Z",
+            Action::ActualSize => "X",
+            Action::Background => "B",
+            Action::BackgroundColor => "C",
+            Action::NearestFilter => "N",
+            Action::Inspect => "P",
+            Action::OverlayOrigin => "O",
+            Action::OverlayLayers => "L",
+            Action::OverlayBounds => "U",
+            Action::OverlayGrid => "T",
+            Action::FewerOnionFrames => "[",
+            Action::MoreOnionFrames => "]",
+            Action::NextSprite => "Tab",
+            Action::PreviousSprite => "Shift+Tab",
+            Action::PickSprite => "MouseMiddle",
+            Action::HideSprite => "V",
+            Action::SoloSprite => "S",
+            Action::HighlightSprite => "I",
+            Action::ShowAllSprites => "A",
+            Action::Record => "R",
+            Action::Screenshot => "K",
+            Action::ScreenshotWithBackground => "Shift+K",
+            Action::SlowerPlayback => ",",
+            Action::FasterPlayback => ".",
+            Action::FrameRate => "Q",
+            Action::RecentFiles => "E",
+            Action::NextRecentFile => "W",
+            Action::CompareMode => "D",
+            Action::CloseComparison => "Shift+D",
+            Action::Town => "G",
+            Action::RemoveBuilding => "Delete",
+            Action::Tween => "M",
+            Action::Digit(digit) => ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]
+                .get(*digit as usize)
+                .copied()
+                .unwrap_or_default(),
+            Action::DeleteChar => "Backspace",
+            Action::FinishTyping => "Enter",
+        }
+    }
+}
+
+/// Names of [`Action::Digit`] 0 to 9.
+const DIGIT_NAMES: [&str; 10] = [
+    "digit-0", "digit-1", "digit-2", "digit-3", "digit-4", "digit-5", "digit-6", "digit-7",
+    "digit-8", "digit-9",
+];
+
+/// A key, optionally with Shift held, or a mouse button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Key { key: Key, shift: bool },
+    Mouse(MouseButton),
+}
+
+/// Keys that can be bound, found by their `Debug` name.
+const KEYS: [Key; 70] = [
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::Up,
+    Key::Down,
+    Key::Left,
+    Key::Right,
+    Key::Backquote,
+    Key::Backslash,
+    Key::Backspace,
+    Key::Comma,
+    Key::Delete,
+    Key::End,
+    Key::Enter,
+    Key::Equals,
+    Key::Home,
+    Key::Insert,
+    Key::LeftBracket,
+    Key::Minus,
+    Key::PageDown,
+    Key::PageUp,
+    Key::Period,
+    Key::Quote,
+    Key::RightBracket,
+    Key::Semicolon,
+];
+
+/// Keys bound by their symbol, which may also appear in [`KEYS`] by name.
+const SYMBOLS: [(&str, Key, bool); 21] = [
+    ("0", Key::Num0, false),
+    ("1", Key::Num1, false),
+    ("2", Key::Num2, false),
+    ("3", Key::Num3, false),
+    ("4", Key::Num4, false),
+    ("5", Key::Num5, false),
+    ("6", Key::Num6, false),
+    ("7", Key::Num7, false),
+    ("8", Key::Num8, false),
+    ("9", Key::Num9, false),
+    ("[", Key::LeftBracket, false),
+    ("]", Key::RightBracket, false),
+    (",", Key::Comma, false),
+    (".", Key::Period, false),
+    ("/", Key::Slash, false),
+    ("?", Key::Slash, true),
+    ("-", Key::Minus, false),
+    ("=", Key::Equals, false),
+    ("Slash", Key::Slash, false),
+    ("Tab", Key::Tab, false),
+    ("Space", Key::Space, false),
+];
+
+impl Binding {
+    /// Parses `K`, `Shift+K`, a symbol such as `[` or `?`, or `MouseLeft`/`MouseRight`/`MouseMiddle`.
+    pub fn parse(text: &str) -> Option<Binding> {
+        let text = text.trim();
+        match text.to_lowercase().as_str() {
+            "mouseleft" => return Some(Binding::Mouse(MouseButton::Left)),
+            "mouseright" => return Some(Binding::Mouse(MouseButton::Right)),
+            "mousemiddle" => return Some(Binding::Mouse(MouseButton::Middle)),
+            _ => {}
+        }
+
+        let (name, shift) = match text.split_once('+') {
+            Some((modifier, name))
+                if modifier.eq_ignore_ascii_case("shift") && !name.is_empty() =>
+            {
+                (name, true)
+            }
+            _ => (text, false),
+        };
+
+        if let Some(&(_, key, symbol_shift)) = SYMBOLS
+            .iter()
+            .find(|(symbol, _, _)| symbol.eq_ignore_ascii_case(name))
+        {
+            return Some(Binding::Key {
+                key,
+                shift: shift || symbol_shift,
+            });
+        }
+
+        let key = KEYS
+            .iter()
+            .find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))?;
+        Some(Binding::Key { key: *key, shift })
+    }
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Binding::Key {
+                key: Key::Slash,
+                shift: true,
+            } => write!(f, "?"),
+            Binding::Key { key, shift } => {
+                let name = SYMBOLS
+                    .iter()
+                    .find(|(symbol, symbol_key, symbol_shift)| {
+                        symbol_key == key && !symbol_shift && symbol.len() == 1
+                    })
+                    .map_or(format!("{:?}", key), |(symbol, _, _)| symbol.to_string());
+                if *shift {
+                    write!(f, "Shift+{}", name)
+                } else {
+                    write!(f, "{}", name)
+                }
+            }
+            Binding::Mouse(button) => write!(f, "Mouse{:?}", button),
+        }
+    }
+}
+
+/// Which bindings trigger each action, the defaults overridden from the settings file.
+pub struct Bindings {
+    table: Vec<(Action, Vec<Binding>)>,
+}
+
+impl Bindings {
+    /// Applies `bind <action> = <keys>` overrides, returning a warning for each one not understood
+    /// and for each binding left on more than one action, where the first listed wins.
+    pub fn new(overrides: &[(String, String)]) -> (Bindings, Vec<String>) {
+        let mut warnings = vec![];
+        let mut table = Action::ALL
+            .iter()
+            .map(|&action| {
+                (
+                    action,
+                    parse_list(action.default_bindings()).unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<(Action, Vec<Binding>)>>();
+
+        for (name, keys) in overrides {
+            let Some(entry) = table.iter_mut().find(|(action, _)| action.name() == name) else {
+                warnings.push(format!("unknown action {}", name));
+                continue;
+            };
+            match parse_list(keys) {
+                Some(bindings) if entry.0 == Action::Click => {
+                    let (buttons, keys) =
+                        bindings
+                            .into_iter()
+                            .partition::<Vec<Binding>, _>(|binding| {
+                                matches!(binding, Binding::Mouse(_))
+                            });
+                    for key in keys {
+                        warnings.push(format!(
+                            "{} is not a mouse button, ignored for {}",
+                            key, name
+                        ));
+                    }
+                    entry.1 = buttons;
+                }
+                Some(bindings) => entry.1 = bindings,
+                None => warnings.push(format!("could not read keys {} for {}", keys, name)),
+            }
+        }
+
+        for (index, (action, bindings)) in table.iter().enumerate() {
+            for binding in bindings {
+                // Clicks over the list and menu come first, elsewhere the button does the rest
+                if let Some((other, _)) = table[index + 1..].iter().find(|(other, others)| {
+                    others.contains(binding) && ![*action, *other].contains(&Action::Click)
+                }) {
+                    warnings.push(format!(
+                        "{} is bound to both {} and {}, using it for {}",
+                        binding,
+                        action.name(),
+                        other.name(),
+                        action.name()
+                    ));
+                }
+            }
+        }
+
+        (Bindings { table }, warnings)
+    }
+
+    /// The action for a key, preferring a binding with the same Shift state.
+    pub fn key_action(&self, key: Key, shift: bool) -> Option<Action> {
+        self.find(Binding::Key { key, shift })
+            .or_else(|| shift.then(|| self.find(Binding::Key { key, shift: false }))?)
+    }
+
+    /// The action for a mouse button, other than [`Action::Click`] which [`Bindings::is_click`]
+    /// checks for.
+    pub fn mouse_action(&self, button: MouseButton) -> Option<Action> {
+        self.table
+            .iter()
+            .find(|(action, bindings)| {
+                *action != Action::Click && bindings.contains(&Binding::Mouse(button))
+            })
+            .map(|(action, _)| *action)
+    }
+
+    pub fn is_click(&self, button: MouseButton) -> bool {
+        self.table.iter().any(|(action, bindings)| {
+            *action == Action::Click && bindings.contains(&Binding::Mouse(button))
+        })
+    }
+
+    fn find(&self, binding: Binding) -> Option<Action> {
+        self.table
+            .iter()
+            .find(|(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| *action)
+    }
+
+    /// One line per action with its bindings, for the help overlay.
+    pub fn help_text(&self) -> String {
+        // Digits are listed once, as a range while they keep their defaults
+        let digits = self
+            .table
+            .iter()
+            .filter(|(action, _)| matches!(action, Action::Digit(_)))
+            .collect::<Vec<_>>();
+        let default_digits = digits.iter().all(|(action, bindings)| {
+            parse_list(action.default_bindings()).as_ref() == Some(bindings)
+        });
+
+        let mut lines = self
+            .table
+            .iter()
+            .filter(|(action, _)| !default_digits || !matches!(action, Action::Digit(_)))
+            .map(|(action, bindings)| {
+                let keys = bindings
+                    .iter()
+                    .map(Binding::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("{:<14} {}", keys, action.description())
+            })
+            .collect::<Vec<String>>();
+        if default_digits {
+            lines.push(format!("{:<14} {}", "0-9", Action::Digit(0).description()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Comma separated bindings, `None` if any of them can't be read. A lone `,` binds the comma key.
+fn parse_list(text: &str) -> Option<Vec<Binding>> {
+    if text.trim() == "," {
+        return Binding::parse(",").map(|binding| vec![binding]);
+    }
+    text.split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(Binding::parse)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: Key, shift: bool) -> Binding {
+        Binding::Key { key, shift }
+    }
+
+    #[test]
+    fn parses_keys_symbols_and_buttons() {
+        assert_eq!(Binding::parse("k"), Some(key(Key::K, false)));
+        assert_eq!(Binding::parse(" Shift+K "), Some(key(Key::K, true)));
+        assert_eq!(Binding::parse("shift+pageup"), Some(key(Key::PageUp, true)));
+        assert_eq!(Binding::parse("["), Some(key(Key::LeftBracket, false)));
+        assert_eq!(Binding::parse("?"), Some(key(Key::Slash, true)));
+        assert_eq!(Binding::parse("Shift+/"), Some(key(Key::Slash, true)));
+        assert_eq!(Binding::parse("7"), Some(key(Key::Num7, false)));
+        assert_eq!(Binding::parse("Num7"), Some(key(Key::Num7, false)));
+        assert_eq!(
+            Binding::parse("MouseMiddle"),
+            Some(Binding::Mouse(MouseButton::Middle))
+        );
+        assert_eq!(Binding::parse("Ctrl+K"), None);
+        assert_eq!(Binding::parse("Shift+"), None);
+        assert_eq!(Binding::parse("Nothing"), None);
+    }
+
+    #[test]
+    fn bindings_display_as_they_parse() {
+        for text in [
+            "K",
+            "Shift+K",
+            "?",
+            "[",
+            "7",
+            "F12",
+            "MouseLeft",
+            "Backspace",
+        ] {
+            assert_eq!(Binding::parse(text).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn parses_lists() {
+        assert_eq!(
+            parse_list("Shift+K, F12"),
+            Some(vec![key(Key::K, true), key(Key::F12, false)])
+        );
+        assert_eq!(parse_list(" , "), Some(vec![key(Key::Comma, false)]));
+        assert_eq!(
+            parse_list("H, ?, "),
+            Some(vec![key(Key::H, false), key(Key::Slash, true)])
+        );
+        assert_eq!(parse_list("H, Nothing"), None);
+    }
+
+    #[test]
+    fn defaults_parse_without_conflicts() {
+        for action in Action::ALL {
+            assert!(parse_list(action.default_bindings()).is_some_and(|keys| !keys.is_empty()));
+        }
+        let (bindings, warnings) = Bindings::new(&[]);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(
+            bindings.key_action(Key::K, true),
+            Some(Action::ScreenshotWithBackground)
+        );
+        assert_eq!(bindings.key_action(Key::R, true), Some(Action::Record));
+        assert_eq!(
+            bindings.key_action(Key::Num3, false),
+            Some(Action::Digit(3))
+        );
+    }
+
+    #[test]
+    fn settings_override_defaults() {
+        let overrides = [
+            (String::from("freeze"), String::from("J, F5")),
+            (String::from("record"), String::from("F")),
+            (String::from("teleport"), String::from("Y")),
+            (String::from("fit"), String::from("Nothing")),
+        ];
+        let (bindings, warnings) = Bindings::new(&overrides);
+
+        assert_eq!(bindings.key_action(Key::J, false), Some(Action::Freeze));
+        assert_eq!(bindings.key_action(Key::F5, false), Some(Action::Freeze));
+        // Freeze no longer has F, which record now uses
+        assert_eq!(bindings.key_action(Key::F, false), Some(Action::Record));
+        assert_eq!(bindings.key_action(Key::R, false), None);
+        // Keys that can't be read leave the default
+        assert_eq!(bindings.key_action(Key::Z, false), Some(Action::Fit));
+        assert_eq!(
+            warnings,
+            [
+                "unknown action teleport",
+                "could not read keys Nothing for fit"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        let overrides = [(String::from("record"), String::from("B"))];
+        let (bindings, warnings) = Bindings::new(&overrides);
+
+        assert_eq!(
+            warnings,
+            ["B is bound to both background and record, using it for background"]
+        );
+        assert_eq!(bindings.key_action(Key::B, false), Some(Action::Background));
+    }
+
+    #[test]
+    fn clicks_follow_their_binding() {
+        let (bindings, _) = Bindings::new(&[]);
+        assert!(bindings.is_click(MouseButton::Left));
+        assert_eq!(bindings.mouse_action(MouseButton::Left), Some(Action::Pan));
+
+        let overrides = [
+            (String::from("click"), String::from("MouseRight, K")),
+            (String::from("pan"), String::from("MouseMiddle")),
+        ];
+        let (bindings, warnings) = Bindings::new(&overrides);
+
+        assert!(bindings.is_click(MouseButton::Right));
+        assert!(!bindings.is_click(MouseButton::Left));
+        assert_eq!(
+            bindings.mouse_action(MouseButton::Right),
+            Some(Action::CycleAnimation)
+        );
+        assert_eq!(
+            warnings,
+            [
+                "K is not a mouse button, ignored for click",
+                "MouseMiddle is bound to both pan and pick-sprite, using it for pan"
+            ]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use tetra::graphics::{self, BlendState, Canvas, Color, DrawParams, FilterMode};
+use tetra::math::Vec2;
+use tetra::Context;
+
+use crate::scene::Scene;
+use crate::CANVAS_SIZE;
+
+/// How the second asset is shown against the first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompareMode {
+    /// Each asset in its own half of the window.
+    SideBySide,
+    /// Both in the same place, black where they match and bright where they differ.
+    Difference,
+}
+
+/// A second asset played in step with the main scene, e.g. a game update against the original.
+pub struct Comparison {
+    pub scene: Scene,
+    pub mode: CompareMode,
+    /// Whether the main scene's current animation exists in this asset.
+    pub matched: bool,
+    canvas: Canvas,
+    /// Each asset minus the other, added together for the absolute difference.
+    differences: [Canvas; 2],
+}
+
+impl Comparison {
+    pub fn new(
+        ctx: &mut Context,
+        scene: Scene,
+        filter_mode: FilterMode,
+    ) -> tetra::Result<Comparison> {
+        let mut canvas = || Canvas::new(ctx, CANVAS_SIZE as i32, CANVAS_SIZE as i32);
+        let mut comparison = Comparison {
+            canvas: canvas()?,
+            differences: [canvas()?, canvas()?],
+            scene,
+            mode: CompareMode::SideBySide,
+            matched: true,
+        };
+        comparison.set_filter_mode(ctx, filter_mode);
+        Ok(comparison)
+    }
+
+    pub fn set_filter_mode(&mut self, ctx: &mut Context, filter_mode: FilterMode) {
+        self.scene.set_filter_mode(ctx, filter_mode);
+        self.canvas.set_filter_mode(ctx, filter_mode);
+        for canvas in &mut self.differences {
+            canvas.set_filter_mode(ctx, filter_mode);
+        }
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CompareMode::SideBySide => CompareMode::Difference,
+            CompareMode::Difference => CompareMode::SideBySide,
+        };
+    }
+
+    /// Follows the zoom, pan, timer, animations and sprite filter of `main`, matching animations
+    /// by name, and renders the current tick. Called before `main` draws so both use one tick.
+    pub fn update(&mut self, ctx: &mut Context, main: &Scene, onion_skin: usize, tween: bool) {
+        self.scene.keep_view(main);
+        self.matched = main
+            .bsv3
+            .animations
+            .get(main.animation)
+            .is_some_and(|animation| self.scene.find_animation(&animation.name).is_some());
+
+        if self.matched {
+            // The clock is taken from `main` on every update
+            self.scene.draw(ctx, &self.canvas, onion_skin, 0.0, tween);
+        } else {
+            // Nothing to compare against rather than a different animation
+            graphics::set_canvas(ctx, &self.canvas);
+            graphics::clear(ctx, Color::rgba(0.0, 0.0, 0.0, 0.0));
+            graphics::reset_canvas(ctx);
+            self.scene.drawn.clear();
+        }
+    }
+
+    /// The rendered tick, drawn like the main scene's canvas.
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    /// Draws `|main - this|` per channel with `params`, meant to go over black.
+    pub fn draw_difference(&self, ctx: &mut Context, main: &Canvas, params: DrawParams) {
+        let pairs = [(main, &self.canvas), (&self.canvas, main)];
+        for (difference, (from, minus)) in self.differences.iter().zip(pairs) {
+            graphics::set_canvas(ctx, difference);
+            graphics::clear(ctx, Color::rgba(0.0, 0.0, 0.0, 0.0));
+            graphics::set_blend_state(ctx, BlendState::add(true));
+            from.draw(ctx, Vec2::zero());
+            // Clamps at zero, so each canvas holds one side of the difference
+            graphics::set_blend_state(ctx, BlendState::subtract(true));
+            minus.draw(ctx, Vec2::zero());
+            graphics::reset_canvas(ctx);
+        }
+
+        graphics::set_blend_state(ctx, BlendState::add(true));
+        for difference in &self.differences {
+            difference.draw(ctx, params.clone());
+        }
+        graphics::reset_blend_state(ctx);
+    }
+
+    /// Names the compared file and the mode, and warns when the animation is missing from it.
+    pub fn label(&self, main: &Scene) -> String {
+        let file_name = std::path::Path::new(&self.scene.path)
+            .file_name()
+            .map_or(self.scene.path.clone(), |name| {
+                name.to_string_lossy().to_string()
+            });
+        let mode = match self.mode {
+            CompareMode::SideBySide => "Side by side",
+            CompareMode::Difference => "Difference",
+        };
+        let missing = match main.bsv3.animations.get(main.animation) {
+            Some(animation) if !self.matched => format!(" (no {})", animation.name),
+            _ => String::new(),
+        };
+        format!("{} with {}{}", mode, file_name, missing)
+    }
+}
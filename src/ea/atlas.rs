@@ -0,0 +1,234 @@
+use std::io::{Error, ErrorKind};
+
+use crate::ea::bsv3::BSV3;
+use crate::ea::rgb::RGB;
+
+/// Pixels copied from around each sprite region, so filtering at its edges samples the same
+/// neighbours as in the original atlas.
+const BORDER: u32 = 1;
+
+/// Widths tried between the square root of the packed area and twice that.
+const WIDTH_STEPS: u32 = 16;
+
+/// Atlas sizes and the area of the sprite regions in them, from [`BSV3::repack`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Repacked {
+    pub before: (u32, u32),
+    pub after: (u32, u32),
+    /// Pixels inside distinct sprite regions.
+    pub sprite_area: u64,
+}
+
+impl Repacked {
+    /// Share of the original atlas covered by sprites.
+    pub fn utilisation_before(&self) -> f64 {
+        utilisation(self.sprite_area, self.before)
+    }
+
+    pub fn utilisation_after(&self) -> f64 {
+        utilisation(self.sprite_area, self.after)
+    }
+}
+
+fn utilisation(area: u64, (width, height): (u32, u32)) -> f64 {
+    (area as f64 / (width as u64 * height as u64).max(1) as f64).min(1.0)
+}
+
+/// Places rectangles of `sizes` on shelves, tallest first, with `padding` pixels between them.
+/// Tries several widths and keeps the smallest atlas, sized to powers of two if `power_of_two`.
+/// Returns the atlas width and height and the top left of each rectangle.
+pub fn pack(sizes: &[(u32, u32)], padding: u32, power_of_two: bool) -> (u32, u32, Vec<(u32, u32)>) {
+    let mut order = (0..sizes.len()).collect::<Vec<usize>>();
+    order.sort_by_key(|&index| std::cmp::Reverse((sizes[index].1, sizes[index].0)));
+
+    let widest = sizes
+        .iter()
+        .map(|&(width, _)| width)
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let area = sizes
+        .iter()
+        .map(|&(width, height)| (width + padding) as f64 * (height + padding) as f64)
+        .sum::<f64>();
+    let candidates = if power_of_two {
+        let total = sizes.iter().map(|&(width, _)| width + padding).sum::<u32>();
+        let mut widths = vec![widest.next_power_of_two()];
+        while *widths.last().unwrap() < total {
+            widths.push(widths.last().unwrap() * 2);
+        }
+        widths
+    } else {
+        let side = area.sqrt();
+        (0..=WIDTH_STEPS)
+            .map(|step| (side * (1.0 + step as f64 / WIDTH_STEPS as f64)) as u32)
+            .map(|width| width.max(widest))
+            .chain([widest])
+            .collect()
+    };
+
+    candidates
+        .into_iter()
+        .map(|width| {
+            let (used_width, height, positions) = shelves(sizes, &order, width, padding);
+            if power_of_two {
+                (width, height.next_power_of_two(), positions)
+            } else {
+                (used_width, height, positions)
+            }
+        })
+        .min_by_key(|&(width, height, _)| (width as u64 * height as u64, width.max(height)))
+        .unwrap_or((1, 1, vec![]))
+}
+
+/// Fills rows of at most `width` in `order`, returning the width and height used.
+fn shelves(
+    sizes: &[(u32, u32)],
+    order: &[usize],
+    width: u32,
+    padding: u32,
+) -> (u32, u32, Vec<(u32, u32)>) {
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    let mut used_width = 0;
+    for &index in order {
+        let (item_width, item_height) = sizes[index];
+        if x > 0 && x + item_width > width {
+            y += shelf_height + padding;
+            x = 0;
+            shelf_height = 0;
+        }
+        positions[index] = (x, y);
+        used_width = used_width.max(x + item_width);
+        x += item_width + padding;
+        shelf_height = shelf_height.max(item_height);
+    }
+    (used_width.max(1), (y + shelf_height).max(1), positions)
+}
+
+impl BSV3 {
+    /// Rebuilds `texture` from only the sprite regions layers use, packed tightly with `padding`
+    /// pixels between them, and moves the sprites to match. Unused sprites are removed first and
+    /// sprites sharing a region keep sharing it.
+    ///
+    /// Each region takes a one pixel border of what surrounded it, so the asset renders exactly
+    /// as before.
+    pub fn repack(
+        &mut self,
+        texture: &mut RGB,
+        padding: u32,
+        power_of_two: bool,
+    ) -> std::io::Result<Repacked> {
+        texture.check_writable()?;
+        self.remove_unused_sprites();
+
+        let mut regions: Vec<(u16, u16, u16, u16)> = vec![];
+        for sprite in &self.sprites {
+            let region = (sprite.x, sprite.y, sprite.width, sprite.height);
+            if !regions.contains(&region) {
+                regions.push(region);
+            }
+        }
+
+        let sizes = regions
+            .iter()
+            .map(|&(_, _, width, height)| (width as u32 + 2 * BORDER, height as u32 + 2 * BORDER))
+            .collect::<Vec<(u32, u32)>>();
+        let (width, height, positions) = pack(&sizes, padding, power_of_two);
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("repacked atlas of {}x{} is too large", width, height),
+            ));
+        }
+
+        let mut data = vec![0; width as usize * height as usize * 4];
+        for (&(x, y, region_width, region_height), &(left, top)) in regions.iter().zip(&positions) {
+            let border = BORDER as i32;
+            for row in -border..region_height as i32 + border {
+                for column in -border..region_width as i32 + border {
+                    let pixel = texture.pixel(x as i32 + column, y as i32 + row);
+                    let to_x = (left as i32 + border + column) as usize;
+                    let to_y = (top as i32 + border + row) as usize;
+                    let offset = (to_y * width as usize + to_x) * 4;
+                    data[offset..offset + 4].copy_from_slice(&pixel);
+                }
+            }
+        }
+
+        for sprite in &mut self.sprites {
+            let region = (sprite.x, sprite.y, sprite.width, sprite.height);
+            let index = regions.iter().position(|&other| other == region).unwrap();
+            sprite.x = (positions[index].0 + BORDER) as u16;
+            sprite.y = (positions[index].1 + BORDER) as u16;
+        }
+
+        let repacked = Repacked {
+            before: (texture.width as u32, texture.height as u32),
+            after: (width, height),
+            sprite_area: regions
+                .iter()
+                .map(|&(_, _, width, height)| width as u64 * height as u64)
+                .sum(),
+        };
+        texture.width = width as u16;
+        texture.height = height as u16;
+        texture.data = data;
+        Ok(repacked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_without_overlap() {
+        let sizes = [(10, 4), (3, 8), (6, 6), (6, 2), (1, 1)];
+        for power_of_two in [false, true] {
+            let (width, height, positions) = pack(&sizes, 1, power_of_two);
+            let rects = positions
+                .iter()
+                .zip(&sizes)
+                .map(|(&(x, y), &(w, h))| (x, y, x + w, y + h))
+                .collect::<Vec<_>>();
+            for (index, a) in rects.iter().enumerate() {
+                assert!(a.2 <= width && a.3 <= height);
+                for b in &rects[index + 1..] {
+                    assert!(a.2 < b.0 || b.2 < a.0 || a.3 < b.1 || b.3 < a.1);
+                }
+            }
+            if power_of_two {
+                assert!(width.is_power_of_two() && height.is_power_of_two());
+            }
+        }
+    }
+
+    #[test]
+    fn repacked_sprites_keep_their_pixels() {
+        let mut bsv3 = BSV3::new(String::from("building.bsv3"));
+        bsv3.parse().unwrap();
+        let mut texture = RGB::new(bsv3.texture_path());
+        texture.parse().unwrap();
+        let original = bsv3.sprites.clone();
+        let pixels = |texture: &RGB, x: u16, y: u16, width: u16, height: u16| {
+            (0..height as i32)
+                .flat_map(|row| (0..width as i32).map(move |column| (column, row)))
+                .map(|(column, row)| texture.pixel(x as i32 + column, y as i32 + row))
+                .collect::<Vec<_>>()
+        };
+        let before = original
+            .iter()
+            .map(|sprite| pixels(&texture, sprite.x, sprite.y, sprite.width, sprite.height))
+            .collect::<Vec<_>>();
+
+        let repacked = bsv3.repack(&mut texture, 0, false).unwrap();
+        assert!(repacked.utilisation_after() > repacked.utilisation_before());
+        for (sprite, before) in bsv3.sprites.iter().zip(before) {
+            assert_eq!(
+                pixels(&texture, sprite.x, sprite.y, sprite.width, sprite.height),
+                before
+            );
+        }
+    }
+}
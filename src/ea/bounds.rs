@@ -0,0 +1,104 @@
+use crate::ea::bsv3::{Animation, FrameRef, BSV3};
+use crate::ea::draw_list::DrawCommand;
+use crate::ea::geometry::Rect;
+use crate::ea::rgb::RGB;
+
+impl BSV3 {
+    /// Bounds in asset space of everything drawn for a frame or group, each sprite counting by
+    /// its entry in `extents`. `None` if nothing is drawn.
+    pub fn frame_bounds(&self, frame_ref: FrameRef, extents: &[Option<Rect>]) -> Option<Rect> {
+        Self::commands_bounds(&self.draw_list(frame_ref), extents)
+    }
+
+    /// Union of [`BSV3::frame_bounds`] over every step of `animation`.
+    pub fn animation_bounds(
+        &self,
+        animation: &Animation,
+        extents: &[Option<Rect>],
+    ) -> Option<Rect> {
+        animation
+            .iter_frames()
+            .filter_map(|frame_ref| self.frame_bounds(frame_ref, extents))
+            .reduce(|bounds, frame| bounds.union(&frame))
+    }
+
+    /// Union of [`BSV3::animation_bounds`] over every animation.
+    pub fn bounds(&self, extents: &[Option<Rect>]) -> Option<Rect> {
+        self.animations
+            .iter()
+            .filter_map(|animation| self.animation_bounds(animation, extents))
+            .reduce(|bounds, animation| bounds.union(&animation))
+    }
+
+    /// The part of each sprite that counts towards bounds, relative to the sprite, for the
+    /// functions above. With a texture only the non-transparent part of each sprite counts,
+    /// otherwise the whole sprite quad. Scans the texture, so compute it once per texture.
+    pub fn sprite_extents(&self, texture: Option<&RGB>) -> Vec<Option<Rect>> {
+        self.sprites
+            .iter()
+            .map(|sprite| {
+                let whole = Rect::new(0.0, 0.0, sprite.width as f32, sprite.height as f32);
+                match texture {
+                    Some(texture) => texture.opaque_rect(&Rect::new(
+                        sprite.x as f32,
+                        sprite.y as f32,
+                        whole.width,
+                        whole.height,
+                    )),
+                    None => Some(whole),
+                }
+            })
+            .collect()
+    }
+
+    fn commands_bounds(commands: &[DrawCommand], extents: &[Option<Rect>]) -> Option<Rect> {
+        commands
+            .iter()
+            .filter(|command| command.alpha > 0.0)
+            .filter_map(|command| {
+                let extent = extents.get(command.sprite)?.as_ref()?;
+                Some(command.transform.transform_rect_bounds(extent))
+            })
+            .reduce(|bounds, quad| bounds.union(&quad))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn building() -> (BSV3, RGB) {
+        let mut bsv3 = BSV3::new(String::from("building.bsv3"));
+        bsv3.parse().unwrap();
+        let mut texture = RGB::new(bsv3.texture_path());
+        texture.parse().unwrap();
+        (bsv3, texture)
+    }
+
+    #[test]
+    fn frame_bounds_cover_every_sprite_quad() {
+        let (bsv3, _) = building();
+        let bounds = bsv3
+            .frame_bounds(FrameRef::Frame(0), &bsv3.sprite_extents(None))
+            .unwrap();
+
+        assert!((bounds.x - -76.93725).abs() < 1e-3);
+        assert!((bounds.y - -523.862).abs() < 1e-3);
+        assert!((bounds.right() - 79.06275).abs() < 1e-3);
+        assert!((bounds.bottom() - -0.862).abs() < 1e-3);
+    }
+
+    #[test]
+    fn alpha_tight_bounds_fit_inside_quad_bounds() {
+        let (bsv3, texture) = building();
+        let quads = bsv3.sprite_extents(None);
+        let loose = bsv3.animation_bounds(&bsv3.animations[0], &quads).unwrap();
+        let tight = bsv3
+            .animation_bounds(&bsv3.animations[0], &bsv3.sprite_extents(Some(&texture)))
+            .unwrap();
+
+        assert!(tight.x >= loose.x && tight.y >= loose.y);
+        assert!(tight.right() <= loose.right() && tight.bottom() <= loose.bottom());
+        assert_eq!(bsv3.bounds(&quads), Some(loose));
+    }
+}
@@ -1,12 +1,8 @@
-#![allow(unused_variables)]
-#![allow(dead_code)]
-
 use colored::Colorize;
 use std::cmp::max;
 
 use crate::ea::file_buffer::FileBuffer;
-
-static SAVE_RGB: bool = false;
+use crate::ea::geometry::Affine2;
 
 #[derive(Debug, Clone)]
 pub struct Sprite {
@@ -30,29 +26,101 @@ pub struct Layer {
     pub alpha: u8,
 }
 
+impl Layer {
+    /// Scale, skew and translation of the layer, without the offsets `BSV3::draw_command` adds
+    /// for negative scale and skew.
+    pub fn affine(&self) -> Affine2 {
+        Affine2::new(
+            self.scale_x,
+            self.skew_h,
+            self.skew_v,
+            self.scale_y,
+            self.x,
+            self.y,
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Frame {
     pub layer_count: u16,
+    /// Byte after the layer count in 0x0103/0x0203 files, kept for writing.
+    pub unknown: u8,
     pub layers: Vec<Layer>,
 }
 
 #[derive(Debug, Clone)]
 pub struct FrameGroup {
     pub frame_count: u16,
+    /// Byte after the frame count, kept for writing.
+    pub unknown: u8,
     pub frames: Vec<usize>,
 }
 
+/// What the `start..=end` range of an [`Animation`] indexes into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationKind {
+    /// Formats 0x0103/0x0203/0x0104: indices into `BSV3::frames`.
+    Frames,
+    /// Format 0x0303: indices into `BSV3::groups`, each expanding to several frames.
+    Groups,
+}
+
+/// A single step of an animation, resolved to either a frame or a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameRef {
+    Frame(usize),
+    Group(usize),
+}
+
+impl FrameRef {
+    /// The raw frame or group index.
+    pub fn index(&self) -> usize {
+        match *self {
+            FrameRef::Frame(index) | FrameRef::Group(index) => index,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Animation {
     pub name: String,
     pub start: u16,
+    /// Last step of the animation, inclusive.
     pub end: u16,
+    pub kind: AnimationKind,
+}
+
+impl Animation {
+    /// Number of steps in `start..=end`. Malformed ranges with `end < start` count as one step.
+    pub fn frame_count(&self) -> usize {
+        self.end.saturating_sub(self.start) as usize + 1
+    }
+
+    /// The step shown at `tick`, looping back to `start` after `end`.
+    pub fn frame_at(&self, tick: usize) -> FrameRef {
+        self.step(self.start as usize + tick % self.frame_count())
+    }
+
+    /// Every step of the animation in playback order.
+    pub fn iter_frames(&self) -> impl Iterator<Item = FrameRef> + '_ {
+        (0..self.frame_count()).map(|tick| self.frame_at(tick))
+    }
+
+    fn step(&self, index: usize) -> FrameRef {
+        match self.kind {
+            AnimationKind::Frames => FrameRef::Frame(index),
+            AnimationKind::Groups => FrameRef::Group(index),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct BSV3 {
     pub file: FileBuffer,
     pub format: u16,
+    /// Float after the format in 0x0303 files, kept for writing.
+    pub header_unknown: f32,
     pub data_type: u8,
     pub image_name: String,
     pub sprites: Vec<Sprite>,
@@ -63,90 +131,127 @@ pub struct BSV3 {
 
 impl BSV3 {
     pub fn new(name: String) -> BSV3 {
-        let file = FileBuffer::new(name.clone());
+        Self::open(name).unwrap()
+    }
 
-        BSV3 {
+    /// Like [`BSV3::new`] but returns the error if the file can't be opened.
+    pub fn open(name: String) -> std::io::Result<BSV3> {
+        let file = FileBuffer::open(name)?;
+
+        Ok(BSV3 {
             file,
             format: 0,
+            header_unknown: 0.0,
             data_type: 0,
             image_name: String::from(""),
             sprites: vec![],
             frames: vec![],
             groups: vec![],
             animations: vec![],
+        })
+    }
+
+    /// Path of the texture atlas, `image_name` if the file names one or `<name>.rgb` otherwise.
+    pub fn texture_path(&self) -> String {
+        if self.image_name.is_empty() {
+            format!("{}{}.rgb", self.file.folder, self.file.name)
+        } else {
+            format!("{}{}", self.file.folder, self.image_name)
         }
     }
 
-    pub fn parse(&mut self) {
+    /// Path of the building XML exported next to the asset, which may not exist.
+    pub fn building_xml_path(&self) -> String {
+        format!("{}{}.xml", self.file.folder, self.file.name)
+    }
+
+    /// Frame indices to draw for `frame_ref`, in draw order.
+    ///
+    /// A group expands to its frames back to front; an out of range reference yields nothing.
+    pub fn frames_for(&self, frame_ref: FrameRef) -> Vec<usize> {
+        match frame_ref {
+            FrameRef::Frame(index) if index < self.frames.len() => vec![index],
+            FrameRef::Group(index) if index < self.groups.len() => self.groups[index]
+                .frames
+                .iter()
+                .rev()
+                .copied()
+                .filter(|&frame| frame < self.frames.len())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    pub fn parse(&mut self) -> std::io::Result<()> {
         let start_time = std::time::Instant::now();
-        print!("{} {}.bsv3\n", "Parsing".blue(), self.file.name);
+        println!("{} {}.bsv3", "Parsing".blue(), self.file.name);
 
-        self.format = self.file.read_uint_16();
+        self.format = self.file.read_uint_16()?;
 
         println!("Format: {}", self.format);
 
         if self.format == 0x0104 {
-            self.file.skip(3);
+            self.file.skip(3)?;
         }
 
         if self.format == 0x0303 {
-            self.file.read_float_32();
+            self.header_unknown = self.file.read_float_32()?;
         }
 
-        let sprite_count = self.file.read_uint_16();
+        let sprite_count = self.file.read_uint_16()?;
 
         println!("Sprite count: {}", sprite_count);
 
-        self.data_type = self.file.read_uint_8();
+        self.data_type = self.file.read_uint_8()?;
 
         println!("Data type: {}", self.data_type);
 
         if self.format == 0x0203 {
-            self.image_name = self.file.read_string_8();
+            self.image_name = self.file.read_string_8()?;
             println!("Image name: {}", self.image_name);
         }
 
         let sprites = (0..sprite_count)
-            .map(|_| Sprite {
-                name: self.file.read_string_8(),
-                x: self.file.read_uint_16(),
-                y: self.file.read_uint_16(),
-                width: max(self.file.read_uint_16(), 1),
-                height: max(self.file.read_uint_16(), 1),
+            .map(|_| {
+                Ok(Sprite {
+                    name: self.file.read_string_8()?,
+                    x: self.file.read_uint_16()?,
+                    y: self.file.read_uint_16()?,
+                    width: max(self.file.read_uint_16()?, 1),
+                    height: max(self.file.read_uint_16()?, 1),
+                })
             })
-            .collect::<Vec<Sprite>>();
+            .collect::<std::io::Result<Vec<Sprite>>>()?;
 
-        for i in 0..sprite_count as usize {
-            self.sprites.insert(i, sprites[i].clone());
-        }
+        self.sprites.extend(sprites);
 
         if self.format == 0x0103 || self.format == 0x0203 {
-            let frame_count = self.file.read_uint_16();
+            let frame_count = self.file.read_uint_16()?;
 
             println!("Frame count: {}", frame_count);
 
             let frames = (0..frame_count)
                 .map(|_| {
-                    let layer_count = self.file.read_uint_16();
+                    let layer_count = self.file.read_uint_16()?;
                     // println!("Layer count: {}", layer_count);
-                    self.file.skip(1);
+                    let unknown = self.file.read_uint_8()?;
                     let mut layers = (0..layer_count)
                         .map(|n| {
-                            let sprite = self.file.read_uint_16();
-                            let x = self.file.read_float_32();
-                            let y = self.file.read_float_32();
-                            let scale_x = self.file.read_float_32();
-                            let skew_v = self.file.read_float_32();
-                            let skew_h = self.file.read_float_32();
-                            let scale_y = self.file.read_float_32();
+                            let sprite = self.file.read_uint_16()?;
+                            let x = self.file.read_float_32()?;
+                            let y = self.file.read_float_32()?;
+                            let scale_x = self.file.read_float_32()?;
+                            let skew_v = self.file.read_float_32()?;
+                            let skew_h = self.file.read_float_32()?;
+                            let scale_y = self.file.read_float_32()?;
 
                             let alpha = if self.data_type == 1 {
-                                self.file.read_uint_8()
+                                self.file.read_uint_8()?
                             } else {
                                 255
                             };
 
-                            Layer {
+                            Ok(Layer {
                                 id: n,
                                 sprite,
                                 x,
@@ -156,49 +261,49 @@ impl BSV3 {
                                 skew_v,
                                 scale_y,
                                 alpha,
-                            }
+                            })
                         })
-                        .collect::<Vec<Layer>>();
+                        .collect::<std::io::Result<Vec<Layer>>>()?;
 
                     layers.reverse();
 
-                    Frame {
+                    Ok(Frame {
                         layer_count,
+                        unknown,
                         layers,
-                    }
+                    })
                 })
-                .collect::<Vec<Frame>>();
+                .collect::<std::io::Result<Vec<Frame>>>()?;
 
-            for i in 0..frame_count as usize {
-                self.frames.insert(i, frames[i].clone());
-            }
+            self.frames.extend(frames);
         } else if self.format == 0x0303 {
-            let group_count = self.file.read_uint_16();
+            let group_count = self.file.read_uint_16()?;
 
             println!("Group count: {}", group_count);
 
-            let frame_count = self.file.read_uint_16();
+            let frame_count = self.file.read_uint_16()?;
 
             println!("Frame count: {}", frame_count);
 
             let frames = (0..frame_count)
                 .map(|_| {
-                    let sprite = self.file.read_uint_16();
-                    let x = self.file.read_float_32();
-                    let y = self.file.read_float_32();
-                    let scale_x = self.file.read_float_32();
-                    let skew_v = self.file.read_float_32();
-                    let skew_h = self.file.read_float_32();
-                    let scale_y = self.file.read_float_32();
+                    let sprite = self.file.read_uint_16()?;
+                    let x = self.file.read_float_32()?;
+                    let y = self.file.read_float_32()?;
+                    let scale_x = self.file.read_float_32()?;
+                    let skew_v = self.file.read_float_32()?;
+                    let skew_h = self.file.read_float_32()?;
+                    let scale_y = self.file.read_float_32()?;
 
                     let alpha = if self.data_type == 1 {
-                        self.file.read_uint_8()
+                        self.file.read_uint_8()?
                     } else {
                         255
                     };
 
-                    Frame {
+                    Ok(Frame {
                         layer_count: 1,
+                        unknown: 0,
                         layers: vec![Layer {
                             id: 0,
                             sprite,
@@ -210,56 +315,172 @@ impl BSV3 {
                             scale_y,
                             alpha,
                         }],
-                    }
+                    })
                 })
-                .collect::<Vec<Frame>>();
+                .collect::<std::io::Result<Vec<Frame>>>()?;
 
-            for i in 0..frame_count as usize {
-                self.frames.insert(i, frames[i].clone());
-            }
+            self.frames.extend(frames);
 
             let groups = (0..group_count)
                 .map(|_| {
-                    let frame_count = self.file.read_uint_16();
-                    self.file.read_uint_8();
+                    let frame_count = self.file.read_uint_16()?;
+                    let unknown = self.file.read_uint_8()?;
                     let frames = (0..frame_count)
-                        .map(|_| self.file.read_uint_16() as usize)
-                        .collect::<Vec<usize>>();
+                        .map(|_| Ok(self.file.read_uint_16()? as usize))
+                        .collect::<std::io::Result<Vec<usize>>>()?;
 
-                    FrameGroup {
+                    Ok(FrameGroup {
                         frame_count,
+                        unknown,
                         frames,
-                    }
+                    })
                 })
-                .collect::<Vec<FrameGroup>>();
+                .collect::<std::io::Result<Vec<FrameGroup>>>()?;
 
-            for i in 0..group_count as usize {
-                self.groups.insert(i, groups[i].clone());
-            }
+            self.groups.extend(groups);
         }
 
-        let animation_count = self.file.read_uint_16();
+        let animation_count = self.file.read_uint_16()?;
 
         println!("Animation count: {}", animation_count);
 
+        let kind = if self.format == 0x0303 {
+            AnimationKind::Groups
+        } else {
+            AnimationKind::Frames
+        };
+
         let animations = (0..animation_count)
-            .map(|_| Animation {
-                name: self.file.read_string_8(),
-                start: self.file.read_uint_16(),
-                end: self.file.read_uint_16(),
+            .map(|_| {
+                Ok(Animation {
+                    name: self.file.read_string_8()?,
+                    start: self.file.read_uint_16()?,
+                    end: self.file.read_uint_16()?,
+                    kind,
+                })
             })
-            .collect::<Vec<Animation>>();
+            .collect::<std::io::Result<Vec<Animation>>>()?;
 
-        for i in 0..animation_count as usize {
-            self.animations.insert(i, animations[i].clone());
-        }
+        self.animations.extend(animations);
 
         // println!("Animations: {:#?}", self.animations);
 
         let end_time = std::time::Instant::now();
-        print!("{} {:?}\n", "Done in".green(), end_time - start_time);
+        println!("{} {:?}", "Done in".green(), end_time - start_time);
+        Ok(())
     }
 }
 
 // https://github.com/al1sant0s/tstorgb/blob/main/src/tstorgb/parsers/bsv3.py
 // https://github.com/al1sant0s/tstorgb/blob/main/src/tstorgb/parsers/addons/bsv3_addon.py
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animation(start: u16, end: u16, kind: AnimationKind) -> Animation {
+        Animation {
+            name: String::from("test"),
+            start,
+            end,
+            kind,
+        }
+    }
+
+    #[test]
+    fn single_frame_animation_always_shows_start() {
+        let anim = animation(7, 7, AnimationKind::Frames);
+
+        assert_eq!(anim.frame_count(), 1);
+        assert_eq!(anim.frame_at(0), FrameRef::Frame(7));
+        assert_eq!(anim.frame_at(13), FrameRef::Frame(7));
+        assert_eq!(anim.iter_frames().collect::<Vec<_>>(), [FrameRef::Frame(7)]);
+    }
+
+    #[test]
+    fn two_frame_animation_includes_end() {
+        let anim = animation(3, 4, AnimationKind::Frames);
+
+        assert_eq!(anim.frame_count(), 2);
+        assert_eq!(
+            (0..4).map(|tick| anim.frame_at(tick)).collect::<Vec<_>>(),
+            [
+                FrameRef::Frame(3),
+                FrameRef::Frame(4),
+                FrameRef::Frame(3),
+                FrameRef::Frame(4)
+            ]
+        );
+    }
+
+    #[test]
+    fn reversed_range_counts_as_single_frame() {
+        let anim = animation(5, 2, AnimationKind::Frames);
+
+        assert_eq!(anim.frame_count(), 1);
+        assert_eq!(anim.frame_at(3), FrameRef::Frame(5));
+    }
+
+    #[test]
+    fn group_animation_steps_through_groups() {
+        let anim = animation(0, 2, AnimationKind::Groups);
+
+        assert_eq!(anim.frame_count(), 3);
+        assert_eq!(
+            anim.iter_frames().collect::<Vec<_>>(),
+            [FrameRef::Group(0), FrameRef::Group(1), FrameRef::Group(2)]
+        );
+        assert_eq!(anim.frame_at(4), FrameRef::Group(1));
+    }
+
+    #[test]
+    fn groups_expand_to_frames_back_to_front() {
+        let mut bsv3 = BSV3::new(String::from("building.bsv3"));
+        bsv3.frames = vec![
+            Frame {
+                layer_count: 0,
+                unknown: 0,
+                layers: vec![],
+            };
+            3
+        ];
+        bsv3.groups = vec![FrameGroup {
+            frame_count: 3,
+            unknown: 0,
+            frames: vec![0, 2, 1],
+        }];
+
+        assert_eq!(bsv3.frames_for(FrameRef::Group(0)), [1, 2, 0]);
+        assert_eq!(bsv3.frames_for(FrameRef::Frame(2)), [2]);
+        assert!(bsv3.frames_for(FrameRef::Group(1)).is_empty());
+        assert!(bsv3.frames_for(FrameRef::Frame(3)).is_empty());
+    }
+
+    #[test]
+    fn parsed_animations_use_frame_indices() {
+        let mut bsv3 = BSV3::new(String::from("building.bsv3"));
+        bsv3.parse().unwrap();
+
+        for anim in &bsv3.animations {
+            assert_eq!(anim.kind, AnimationKind::Frames);
+            assert!(anim
+                .iter_frames()
+                .all(|frame_ref| !bsv3.frames_for(frame_ref).is_empty()));
+        }
+    }
+
+    #[test]
+    fn truncated_file_is_an_error() {
+        let bytes = std::fs::read("building.bsv3").unwrap();
+        let path = std::env::temp_dir().join("tsto_bsv3_truncated.bsv3");
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+
+        let mut bsv3 = BSV3::new(path.to_string_lossy().to_string());
+        let result = bsv3.parse();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            result.unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use crate::ea::overlay::{TILE_HEIGHT, TILE_WIDTH};
+
+/// Placement data from the building XML exported next to an asset, e.g.
+/// `<Building x="5" z="5" height="11.5" locX="2" locY="1" transImageX="-94.4" transImageY="-0.6" />`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Building {
+    /// Footprint along the grid columns, in tiles (`x`).
+    pub width: u32,
+    /// Footprint along the grid rows, in tiles (`z`).
+    pub depth: u32,
+    pub height: f32,
+    /// Tile of the footprint the image is anchored to.
+    pub loc_x: f32,
+    pub loc_y: f32,
+    /// Offset of the image from that tile, in pixels.
+    pub trans_image_x: f32,
+    pub trans_image_y: f32,
+}
+
+impl Default for Building {
+    /// A single tile with the asset origin at its top corner, for assets without an XML.
+    fn default() -> Building {
+        Building {
+            width: 1,
+            depth: 1,
+            height: 0.0,
+            loc_x: 0.0,
+            loc_y: 0.0,
+            trans_image_x: 0.0,
+            trans_image_y: 0.0,
+        }
+    }
+}
+
+impl Building {
+    pub fn open(path: &str) -> std::io::Result<Building> {
+        let xml = std::fs::read_to_string(path)?;
+        Building::parse(&xml).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "no valid <Building> element",
+            )
+        })
+    }
+
+    /// Reads the attributes of the first `<Building>` element. Missing attributes keep their
+    /// default, `None` if there is no such element or a value isn't a number.
+    pub fn parse(xml: &str) -> Option<Building> {
+        let start = xml.find("<Building")? + "<Building".len();
+        let end = start + xml[start..].find('>')?;
+
+        let mut building = Building::default();
+        for (name, value) in attributes(&xml[start..end])? {
+            match name {
+                "x" => building.width = value.parse().ok()?,
+                "z" => building.depth = value.parse().ok()?,
+                "height" => building.height = value.parse().ok()?,
+                "locX" => building.loc_x = value.parse().ok()?,
+                "locY" => building.loc_y = value.parse().ok()?,
+                "transImageX" => building.trans_image_x = value.parse().ok()?,
+                "transImageY" => building.trans_image_y = value.parse().ok()?,
+                _ => {}
+            }
+        }
+        Some(building)
+    }
+
+    /// Where the asset origin is drawn relative to the top corner of the footprint: the top
+    /// corner of tile (`locX`, `locY`) moved by `transImageX/Y`.
+    pub fn anchor(&self) -> (f32, f32) {
+        let (x, y) = tile_position(self.loc_x, self.loc_y);
+        (x + self.trans_image_x, y + self.trans_image_y)
+    }
+}
+
+/// Tiles covered by a building on the town grid: columns `col..col + width` and rows
+/// `row..row + depth`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Footprint {
+    pub col: i32,
+    pub row: i32,
+    pub width: i32,
+    pub depth: i32,
+}
+
+impl Footprint {
+    pub fn contains(&self, (col, row): (f32, f32)) -> bool {
+        col >= self.col as f32
+            && col < (self.col + self.width) as f32
+            && row >= self.row as f32
+            && row < (self.row + self.depth) as f32
+    }
+
+    /// Top, right, bottom and left corners relative to the top corner of tile (0, 0).
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        let (col, row) = (self.col as f32, self.row as f32);
+        let (right, bottom) = (
+            (self.col + self.width) as f32,
+            (self.row + self.depth) as f32,
+        );
+        [
+            tile_position(col, row),
+            tile_position(right, row),
+            tile_position(right, bottom),
+            tile_position(col, bottom),
+        ]
+    }
+
+    /// Whether `self` has to be drawn before `other`, lying entirely behind it along one axis.
+    ///
+    /// Footprints that are behind each other along different axes sit side by side on screen and
+    /// can't overlap, so neither has to go first.
+    fn is_behind(&self, other: &Footprint) -> bool {
+        let behind =
+            |a: &Footprint, b: &Footprint| a.col + a.width <= b.col || a.row + a.depth <= b.row;
+        let beside = |a: &Footprint, b: &Footprint| {
+            (a.col + a.width <= b.col && b.row + b.depth <= a.row)
+                || (a.row + a.depth <= b.row && b.col + b.width <= a.col)
+        };
+        behind(self, other) && !beside(self, other)
+    }
+}
+
+/// Position of the top corner of tile (`col`, `row`) relative to the top corner of tile (0, 0).
+pub fn tile_position(col: f32, row: f32) -> (f32, f32) {
+    (
+        (col - row) * TILE_WIDTH / 2.0,
+        (col + row) * TILE_HEIGHT / 2.0,
+    )
+}
+
+/// Fractional tile under `(x, y)`, the inverse of [`tile_position`].
+pub fn tile_at((x, y): (f32, f32)) -> (f32, f32) {
+    let (x, y) = (x / (TILE_WIDTH / 2.0), y / (TILE_HEIGHT / 2.0));
+    ((y + x) / 2.0, (y - x) / 2.0)
+}
+
+/// Indices of `footprints` back to front, so nearer buildings cover the ones behind them.
+///
+/// Overlapping footprints have no right order and are drawn by their front corner.
+pub fn draw_order(footprints: &[Footprint]) -> Vec<usize> {
+    let front = |index: usize| {
+        let footprint = &footprints[index];
+        (
+            footprint.col + footprint.width + footprint.row + footprint.depth,
+            index,
+        )
+    };
+
+    let mut remaining = (0..footprints.len()).collect::<Vec<usize>>();
+    remaining.sort_by_key(|&index| front(index));
+
+    let mut order = Vec::with_capacity(footprints.len());
+    while !remaining.is_empty() {
+        // The first building nothing else left has to be drawn before
+        let next = remaining
+            .iter()
+            .position(|&index| {
+                !remaining
+                    .iter()
+                    .any(|&other| other != index && footprints[other].is_behind(&footprints[index]))
+            })
+            .unwrap_or(0);
+        order.push(remaining.remove(next));
+    }
+    order
+}
+
+/// `name="value"` or `name='value'` pairs, `None` if a value isn't quoted or its quote isn't
+/// closed.
+fn attributes(text: &str) -> Option<Vec<(&str, &str)>> {
+    let mut attributes = vec![];
+    let mut rest = text;
+    while let Some(equals) = rest.find('=') {
+        let name = rest[..equals].trim();
+        let value = rest[equals + 1..].trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|&quote| quote == '"' || quote == '\'')?;
+        let value = &value[quote.len_utf8()..];
+        let end = value.find(quote)?;
+        attributes.push((name, &value[..end]));
+        rest = &value[end + quote.len_utf8()..];
+    }
+    Some(attributes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_building_attributes() {
+        let building = Building::parse(
+            r#"<Building x="5" z="5" height="11.5" locX="2" locY="1" transImageX="-94.4373" transImageY="-0.6378" />"#,
+        )
+        .unwrap();
+        assert_eq!((building.width, building.depth), (5, 5));
+        assert_eq!(building.height, 11.5);
+        assert_eq!((building.loc_x, building.loc_y), (2.0, 1.0));
+        assert_eq!(building.trans_image_x, -94.4373);
+
+        assert_eq!(Building::parse("<Other />"), None);
+        assert_eq!(Building::parse(r#"<Building x="wide" />"#), None);
+        assert_eq!(
+            Building::parse("<Building x='3' z='4' />").unwrap().depth,
+            4
+        );
+        assert_eq!(
+            Building::parse("<Building x=\u{201c}5\u{201d} z=\u{201c}5\u{201d} />"),
+            None
+        );
+        assert_eq!(Building::parse("<Building/>"), Some(Building::default()));
+    }
+
+    #[test]
+    fn tile_at_inverts_tile_position() {
+        assert_eq!(tile_at(tile_position(3.0, -2.0)), (3.0, -2.0));
+    }
+
+    #[test]
+    fn draws_buildings_in_front_last() {
+        let footprint = |col, row, width, depth| Footprint {
+            col,
+            row,
+            width,
+            depth,
+        };
+        // A long building behind a small one whose front corner is further back, and one beside
+        // both that can go anywhere
+        let footprints = [
+            footprint(0, 0, 8, 1),
+            footprint(2, 1, 1, 1),
+            footprint(-4, 4, 1, 1),
+        ];
+        let order = draw_order(&footprints);
+        let position = |index| order.iter().position(|&i| i == index);
+        assert!(position(0) < position(1));
+        assert_eq!(order.len(), 3);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::ea::bsv3::{FrameRef, Layer, BSV3};
+use crate::ea::geometry::{Affine2, Rect};
+
+/// A sprite quad positioned in asset space, where the asset origin is `(0, 0)`.
+///
+/// Every renderer draws these in order, so the viewer, the software renderer and the exporters
+/// agree on the geometry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawCommand {
+    /// Index into `BSV3::sprites`.
+    pub sprite: usize,
+    /// Sprite region of the atlas, in pixels.
+    pub source: Rect,
+    /// Maps a point in the sprite, `(0, 0)` to `(width, height)`, into asset space.
+    pub transform: Affine2,
+    /// Layer opacity from 0 to 1.
+    pub alpha: f32,
+}
+
+impl DrawCommand {
+    /// Sprite sized rectangle at the origin, the quad before `transform`.
+    pub fn local_rect(&self) -> Rect {
+        Rect::new(0.0, 0.0, self.source.width, self.source.height)
+    }
+
+    /// Whether `point` in asset space falls inside the transformed quad.
+    pub fn hit_test(&self, point: (f32, f32)) -> bool {
+        let Some(inverse) = self.transform.invert() else {
+            return false;
+        };
+        let (u, v) = inverse.transform_point(point);
+        u >= 0.0 && v >= 0.0 && u < self.source.width && v < self.source.height
+    }
+}
+
+impl BSV3 {
+    /// Quads to draw for a frame, or every frame of a group, back to front.
+    pub fn draw_list(&self, frame_ref: FrameRef) -> Vec<DrawCommand> {
+        self.frames_for(frame_ref)
+            .into_iter()
+            .flat_map(|frame_idx| self.frames[frame_idx].layers.iter())
+            .filter_map(|layer| self.draw_command(layer))
+            .collect()
+    }
+
+    /// The quad for one layer, `None` if it references a missing sprite.
+    pub fn draw_command(&self, layer: &Layer) -> Option<DrawCommand> {
+        let sprite = self.sprites.get(layer.sprite as usize)?;
+        let width = sprite.width as f32;
+        let height = sprite.height as f32;
+
+        // Setup offset fixes for positioning when scaled/skewed
+        let mut offset_x = 0.0;
+        let mut offset_y = 0.0;
+
+        if layer.scale_x < 0.0 {
+            offset_x += width * layer.scale_x.abs();
+        }
+        if layer.skew_h < 0.0 {
+            offset_x += height * layer.skew_h.abs();
+        }
+        if layer.scale_y < 0.0 {
+            offset_y += height * layer.scale_y.abs();
+        }
+        if layer.skew_v < 0.0 {
+            offset_y += width * layer.skew_v.abs();
+        }
+
+        Some(DrawCommand {
+            sprite: layer.sprite as usize,
+            source: Rect::new(sprite.x as f32, sprite.y as f32, width, height),
+            transform: layer
+                .affine()
+                .then(&Affine2::translation(offset_x, offset_y)),
+            alpha: layer.alpha as f32 / 255.0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn building() -> BSV3 {
+        let mut bsv3 = BSV3::new(String::from("building.bsv3"));
+        bsv3.parse().unwrap();
+        bsv3
+    }
+
+    #[test]
+    fn draw_list_keeps_layer_order() {
+        let bsv3 = building();
+        let commands = bsv3.draw_list(FrameRef::Frame(0));
+
+        assert_eq!(
+            commands.iter().map(|c| c.sprite).collect::<Vec<_>>(),
+            bsv3.frames[0]
+                .layers
+                .iter()
+                .map(|layer| layer.sprite as usize)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(commands[0].source, Rect::new(328.0, 0.0, 85.0, 183.0));
+        assert_eq!(commands[0].alpha, 1.0);
+    }
+
+    #[test]
+    fn negative_scale_is_offset_back_over_the_origin() {
+        let bsv3 = building();
+        let mut layer = bsv3.frames[0].layers[0].clone();
+        layer.x = 10.0;
+        layer.y = 20.0;
+        layer.scale_x = -2.0;
+        layer.scale_y = -1.0;
+
+        let command = bsv3.draw_command(&layer).unwrap();
+
+        assert_eq!(
+            command.transform,
+            Affine2::new(-2.0, 0.0, 0.0, -1.0, 180.0, 203.0)
+        );
+        assert!(command.hit_test((100.0, 100.0)));
+        assert!(!command.hit_test((5.0, 100.0)));
+    }
+
+    #[test]
+    fn missing_sprite_is_skipped() {
+        let bsv3 = building();
+        let mut layer = bsv3.frames[0].layers[0].clone();
+        layer.sprite = 99;
+
+        assert!(bsv3.draw_command(&layer).is_none());
+    }
+}
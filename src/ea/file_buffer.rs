@@ -24,7 +24,16 @@ impl FileBuffer {
         Self::with_endian(name, Endian::Little)
     }
 
-    pub fn with_endian(mut path: String, endian: Endian) -> FileBuffer {
+    pub fn with_endian(path: String, endian: Endian) -> FileBuffer {
+        Self::open_with_endian(path, endian).unwrap()
+    }
+
+    /// Like [`FileBuffer::new`] but returns the error if the file can't be opened.
+    pub fn open(name: String) -> std::io::Result<FileBuffer> {
+        Self::open_with_endian(name, Endian::Little)
+    }
+
+    pub fn open_with_endian(mut path: String, endian: Endian) -> std::io::Result<FileBuffer> {
         path = path.replace("\\", "/");
         // println!("Reading file: {}", path);
 
@@ -41,291 +50,288 @@ impl FileBuffer {
         // println!("Folder: {}", folder);
         // println!("Name: {}", name);
 
-        let data = File::open(path).unwrap();
+        let data = File::open(path)?;
 
-        let metadata = data.metadata().unwrap();
-        FileBuffer {
+        let metadata = data.metadata()?;
+        Ok(FileBuffer {
             folder,
             name,
             data,
             read_offset: 0,
             length: metadata.len(),
             endian,
-        }
+        })
     }
 
-    pub fn get_real_offset(&mut self) -> u64 {
-        self.data.stream_position().unwrap()
+    pub fn get_real_offset(&mut self) -> std::io::Result<u64> {
+        self.data.stream_position()
     }
 
-    pub fn skip(&mut self, bytes: usize) {
+    pub fn skip(&mut self, bytes: usize) -> std::io::Result<()> {
         self.read_offset += bytes;
-        self.data
-            .seek(std::io::SeekFrom::Current(bytes as i64))
-            .unwrap();
+        self.data.seek(std::io::SeekFrom::Current(bytes as i64))?;
+        Ok(())
     }
 
-    pub fn seek(&mut self, offset: usize) {
+    pub fn seek(&mut self, offset: usize) -> std::io::Result<()> {
         self.read_offset = offset;
-        self.data
-            .seek(std::io::SeekFrom::Start(offset as u64))
-            .unwrap();
+        self.data.seek(std::io::SeekFrom::Start(offset as u64))?;
+        Ok(())
     }
 
-    pub fn read_uint_8(&mut self) -> u8 {
+    pub fn read_uint_8(&mut self) -> std::io::Result<u8> {
         let mut buffer = [0_u8; size_of::<u8>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<u8>();
-        u8::from_le_bytes(buffer)
+        Ok(u8::from_le_bytes(buffer))
     }
 
-    pub fn read_uint_16(&mut self) -> u16 {
+    pub fn read_uint_16(&mut self) -> std::io::Result<u16> {
         if self.endian == Endian::Big {
             return self.read_uint_16_be();
         }
         self.read_uint_16_le()
     }
 
-    pub fn read_uint_16_le(&mut self) -> u16 {
+    pub fn read_uint_16_le(&mut self) -> std::io::Result<u16> {
         let mut buffer = [0_u8; size_of::<u16>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<u16>();
-        u16::from_le_bytes(buffer)
+        Ok(u16::from_le_bytes(buffer))
     }
 
-    pub fn read_uint_16_be(&mut self) -> u16 {
+    pub fn read_uint_16_be(&mut self) -> std::io::Result<u16> {
         let mut buffer = [0_u8; size_of::<u16>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<u16>();
-        u16::from_be_bytes(buffer)
+        Ok(u16::from_be_bytes(buffer))
     }
 
-    pub fn read_uint_32(&mut self) -> u32 {
+    pub fn read_uint_32(&mut self) -> std::io::Result<u32> {
         if self.endian == Endian::Big {
             return self.read_uint_32_be();
         }
         self.read_uint_32_le()
     }
 
-    pub fn read_uint_32_le(&mut self) -> u32 {
+    pub fn read_uint_32_le(&mut self) -> std::io::Result<u32> {
         let mut buffer = [0_u8; size_of::<u32>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<u32>();
-        u32::from_le_bytes(buffer)
+        Ok(u32::from_le_bytes(buffer))
     }
 
-    pub fn read_uint_32_be(&mut self) -> u32 {
+    pub fn read_uint_32_be(&mut self) -> std::io::Result<u32> {
         let mut buffer = [0_u8; size_of::<u32>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<u32>();
-        u32::from_be_bytes(buffer)
+        Ok(u32::from_be_bytes(buffer))
     }
 
-    pub fn read_uint_64(&mut self) -> u64 {
+    pub fn read_uint_64(&mut self) -> std::io::Result<u64> {
         if self.endian == Endian::Big {
             return self.read_uint_64_be();
         }
         self.read_uint_64_le()
     }
 
-    pub fn read_uint_64_le(&mut self) -> u64 {
+    pub fn read_uint_64_le(&mut self) -> std::io::Result<u64> {
         let mut buffer = [0_u8; size_of::<u64>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<u64>();
-        u64::from_le_bytes(buffer)
+        Ok(u64::from_le_bytes(buffer))
     }
 
-    pub fn read_uint_64_be(&mut self) -> u64 {
+    pub fn read_uint_64_be(&mut self) -> std::io::Result<u64> {
         let mut buffer = [0_u8; size_of::<u64>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<u64>();
-        u64::from_be_bytes(buffer)
+        Ok(u64::from_be_bytes(buffer))
     }
 
-    pub fn read_int_8(&mut self) -> i8 {
+    pub fn read_int_8(&mut self) -> std::io::Result<i8> {
         let mut buffer = [0_u8; size_of::<i8>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<i8>();
-        i8::from_le_bytes(buffer)
+        Ok(i8::from_le_bytes(buffer))
     }
 
-    pub fn read_int_16(&mut self) -> i16 {
+    pub fn read_int_16(&mut self) -> std::io::Result<i16> {
         if self.endian == Endian::Big {
             return self.read_int_16_be();
         }
         self.read_int_16_le()
     }
 
-    pub fn read_int_16_le(&mut self) -> i16 {
+    pub fn read_int_16_le(&mut self) -> std::io::Result<i16> {
         let mut buffer = [0_u8; size_of::<i16>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<i16>();
-        i16::from_le_bytes(buffer)
+        Ok(i16::from_le_bytes(buffer))
     }
 
-    pub fn read_int_16_be(&mut self) -> i16 {
+    pub fn read_int_16_be(&mut self) -> std::io::Result<i16> {
         let mut buffer = [0_u8; size_of::<i16>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<i16>();
-        i16::from_be_bytes(buffer)
+        Ok(i16::from_be_bytes(buffer))
     }
 
-    pub fn read_int_32(&mut self) -> i32 {
+    pub fn read_int_32(&mut self) -> std::io::Result<i32> {
         if self.endian == Endian::Big {
             return self.read_int_32_be();
         }
         self.read_int_32_le()
     }
 
-    pub fn read_int_32_le(&mut self) -> i32 {
+    pub fn read_int_32_le(&mut self) -> std::io::Result<i32> {
         let mut buffer = [0_u8; size_of::<i32>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<i32>();
-        i32::from_le_bytes(buffer)
+        Ok(i32::from_le_bytes(buffer))
     }
 
-    pub fn read_int_32_be(&mut self) -> i32 {
+    pub fn read_int_32_be(&mut self) -> std::io::Result<i32> {
         let mut buffer = [0_u8; size_of::<i32>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<i32>();
-        i32::from_be_bytes(buffer)
+        Ok(i32::from_be_bytes(buffer))
     }
 
-    pub fn read_int_64(&mut self) -> i64 {
+    pub fn read_int_64(&mut self) -> std::io::Result<i64> {
         if self.endian == Endian::Big {
             return self.read_int_64_be();
         }
         self.read_int_64_le()
     }
 
-    pub fn read_int_64_le(&mut self) -> i64 {
+    pub fn read_int_64_le(&mut self) -> std::io::Result<i64> {
         let mut buffer = [0_u8; size_of::<i64>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<i64>();
-        i64::from_le_bytes(buffer)
+        Ok(i64::from_le_bytes(buffer))
     }
 
-    pub fn read_int_64_be(&mut self) -> i64 {
+    pub fn read_int_64_be(&mut self) -> std::io::Result<i64> {
         let mut buffer = [0_u8; size_of::<i64>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<i64>();
-        i64::from_be_bytes(buffer)
+        Ok(i64::from_be_bytes(buffer))
     }
 
-    pub fn read_float_32(&mut self) -> f32 {
+    pub fn read_float_32(&mut self) -> std::io::Result<f32> {
         if self.endian == Endian::Big {
             return self.read_float_32_be();
         }
         self.read_float_32_le()
     }
 
-    pub fn read_float_32_le(&mut self) -> f32 {
+    pub fn read_float_32_le(&mut self) -> std::io::Result<f32> {
         let mut buffer = [0_u8; size_of::<f32>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<f32>();
-        f32::from_le_bytes(buffer)
+        Ok(f32::from_le_bytes(buffer))
     }
 
-    pub fn read_float_32_be(&mut self) -> f32 {
+    pub fn read_float_32_be(&mut self) -> std::io::Result<f32> {
         let mut buffer = [0_u8; size_of::<f32>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<f32>();
-        f32::from_be_bytes(buffer)
+        Ok(f32::from_be_bytes(buffer))
     }
 
-    pub fn read_uint_16_as_float(&mut self) -> f32 {
+    pub fn read_uint_16_as_float(&mut self) -> std::io::Result<f32> {
         if self.endian == Endian::Big {
             return self.read_uint_16_be_as_float();
         }
         self.read_uint_16_le_as_float()
     }
 
-    pub fn read_uint_16_le_as_float(&mut self) -> f32 {
+    pub fn read_uint_16_le_as_float(&mut self) -> std::io::Result<f32> {
         let mut buffer = [0_u8; size_of::<u16>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<u16>();
-        u16::from_le_bytes(buffer) as f32 / 65535.0
+        Ok(u16::from_le_bytes(buffer) as f32 / 65535.0)
     }
 
-    pub fn read_uint_16_be_as_float(&mut self) -> f32 {
+    pub fn read_uint_16_be_as_float(&mut self) -> std::io::Result<f32> {
         let mut buffer = [0_u8; size_of::<u16>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<u16>();
-        u16::from_be_bytes(buffer) as f32 / 65535.0
+        Ok(u16::from_be_bytes(buffer) as f32 / 65535.0)
     }
 
-    pub fn read_int_16_le_as_float(&mut self) -> f32 {
+    pub fn read_int_16_le_as_float(&mut self) -> std::io::Result<f32> {
         let mut buffer = [0_u8; size_of::<i16>()];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += size_of::<i16>();
-        i16::from_le_bytes(buffer) as f32 / 65535.0
+        Ok(i16::from_le_bytes(buffer) as f32 / 65535.0)
     }
 
-    pub fn read_buffer(&mut self, length: usize) -> Vec<u8> {
+    pub fn read_buffer(&mut self, length: usize) -> std::io::Result<Vec<u8>> {
         let mut buffer = vec![0_u8; length];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += length;
-        buffer
+        Ok(buffer)
     }
 
-    pub fn read_remaining(&mut self) -> Vec<u8> {
-        let remaining_length = self.length - self.read_offset as u64;
+    pub fn read_remaining(&mut self) -> std::io::Result<Vec<u8>> {
+        let remaining_length = self.length.saturating_sub(self.read_offset as u64);
         let mut buffer = vec![0_u8; remaining_length as usize];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         // println!("Read remaining: {}", buffer.len());
         self.read_offset = self.length as usize;
-        buffer
+        Ok(buffer)
     }
 
-    pub fn read_string(&mut self, length: usize) -> String {
+    pub fn read_string(&mut self, length: usize) -> std::io::Result<String> {
         let mut buffer = vec![0_u8; length];
-        self.data.read_exact(&mut buffer).unwrap();
+        self.data.read_exact(&mut buffer)?;
         self.read_offset += length;
-        String::from_utf8(buffer)
-            .unwrap()
-            .trim_end_matches(char::from(0))
-            .to_string()
+        let string = String::from_utf8(buffer)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        Ok(string.trim_end_matches(char::from(0)).to_string())
     }
 
-    pub fn read_string_8(&mut self) -> String {
-        let mut length = self.read_uint_8() as usize;
+    pub fn read_string_8(&mut self) -> std::io::Result<String> {
+        let mut length = self.read_uint_8()? as usize;
         if length > 254 {
             length = 0;
         }
         self.read_string(length)
     }
 
-    pub fn read_string_16(&mut self) -> String {
+    pub fn read_string_16(&mut self) -> std::io::Result<String> {
         if self.endian == Endian::Big {
             return self.read_string_16_be();
         }
         self.read_string_16_le()
     }
 
-    pub fn read_string_16_le(&mut self) -> String {
-        let length = self.read_uint_16_le() as usize;
+    pub fn read_string_16_le(&mut self) -> std::io::Result<String> {
+        let length = self.read_uint_16_le()? as usize;
         self.read_string(length)
     }
 
-    pub fn read_string_16_be(&mut self) -> String {
-        let length = self.read_uint_16_be() as usize;
+    pub fn read_string_16_be(&mut self) -> std::io::Result<String> {
+        let length = self.read_uint_16_be()? as usize;
         self.read_string(length)
     }
 
-    pub fn read_string_32(&mut self) -> String {
+    pub fn read_string_32(&mut self) -> std::io::Result<String> {
         if self.endian == Endian::Big {
             return self.read_string_32_be();
         }
         self.read_string_32_le()
     }
 
-    pub fn read_string_32_le(&mut self) -> String {
-        let length = self.read_uint_32_le() as usize;
+    pub fn read_string_32_le(&mut self) -> std::io::Result<String> {
+        let length = self.read_uint_32_le()? as usize;
         self.read_string(length)
     }
 
-    pub fn read_string_32_be(&mut self) -> String {
-        let length = self.read_uint_32_be() as usize;
+    pub fn read_string_32_be(&mut self) -> std::io::Result<String> {
+        let length = self.read_uint_32_be()? as usize;
         self.read_string(length)
     }
 }
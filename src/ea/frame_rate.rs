@@ -0,0 +1,100 @@
+use std::path::Path;
+
+/// Animation steps per second for assets without an override.
+pub const DEFAULT_FRAME_RATE: f64 = 24.0;
+
+/// Slowest and fastest rates accepted from files and the command line, in steps per second.
+pub const MIN_FRAME_RATE: f64 = 1.0;
+pub const MAX_FRAME_RATE: f64 = 240.0;
+
+/// File next to the assets setting the frame rate of some or all of them, e.g.
+///
+/// ```text
+/// # asset name (without .bsv3) or * for every other asset = steps per second
+/// * = 30
+/// bart_skateboard = 24
+/// ```
+pub const OVERRIDE_FILE: &str = "framerates.txt";
+
+/// Path of the override file for the asset at `bsv3_path`, which may not exist.
+pub fn override_path(bsv3_path: &str) -> String {
+    let folder = Path::new(bsv3_path).parent().unwrap_or(Path::new(""));
+    folder.join(OVERRIDE_FILE).to_string_lossy().to_string()
+}
+
+/// Frame rate of the asset at `bsv3_path` from the override file in its folder, or the default.
+pub fn frame_rate_for(bsv3_path: &str) -> f64 {
+    let name = Path::new(bsv3_path)
+        .file_stem()
+        .map_or(String::new(), |name| name.to_string_lossy().to_string());
+    std::fs::read_to_string(override_path(bsv3_path))
+        .ok()
+        .and_then(|overrides| parse_override(&overrides, &name))
+        .unwrap_or(DEFAULT_FRAME_RATE)
+}
+
+/// Frame rate in `value`, clamped to [`MIN_FRAME_RATE`]..=[`MAX_FRAME_RATE`]. `None` unless it is
+/// a finite number above zero, as anything else would stop or overflow the clock.
+pub fn parse_rate(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .map(|rate| rate.clamp(MIN_FRAME_RATE, MAX_FRAME_RATE))
+}
+
+/// Rate `overrides` gives the asset called `name`, its own line winning over `*`. Lines that
+/// aren't `name = rate` with a rate [`parse_rate`] accepts are ignored.
+pub fn parse_override(overrides: &str, name: &str) -> Option<f64> {
+    let mut wildcard = None;
+    for line in overrides.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let Some(rate) = parse_rate(value) else {
+            continue;
+        };
+
+        match key.trim() {
+            key if key == name => return Some(rate),
+            "*" => wildcard = Some(rate),
+            _ => {}
+        }
+    }
+    wildcard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asset_rate_wins_over_wildcard() {
+        let overrides = "# rates\nbart = 24\n* = 30\nlisa = fast\nmaggie = 0\n";
+        assert_eq!(parse_override(overrides, "bart"), Some(24.0));
+        assert_eq!(parse_override(overrides, "homer"), Some(30.0));
+        assert_eq!(parse_override(overrides, "lisa"), Some(30.0));
+        assert_eq!(parse_override(overrides, "maggie"), Some(30.0));
+        assert_eq!(parse_override("bart = 12", "homer"), None);
+    }
+
+    #[test]
+    fn rates_are_finite_and_clamped() {
+        assert_eq!(parse_rate(" 30 "), Some(30.0));
+        assert_eq!(parse_rate("inf"), None);
+        assert_eq!(parse_rate("NaN"), None);
+        assert_eq!(parse_rate("-12"), None);
+        assert_eq!(parse_rate("1e-320"), Some(MIN_FRAME_RATE));
+        assert_eq!(parse_rate("1000"), Some(MAX_FRAME_RATE));
+        assert_eq!(parse_override("* = inf\nbart = 1e-320", "homer"), None);
+        assert_eq!(
+            parse_override("* = inf\nbart = 1e-320", "bart"),
+            Some(MIN_FRAME_RATE)
+        );
+    }
+}
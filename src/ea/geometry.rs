@@ -0,0 +1,253 @@
+/// Axis aligned rectangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    /// Smallest rectangle containing both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+
+    /// Corners clockwise from the top left.
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        [
+            (self.x, self.y),
+            (self.right(), self.y),
+            (self.right(), self.bottom()),
+            (self.x, self.bottom()),
+        ]
+    }
+}
+
+/// 2D affine transform mapping `(x, y)` to `(a * x + b * y + tx, c * x + d * y + ty)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Affine2 {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+/// An [`Affine2`] split into artist friendly parts.
+///
+/// Recomposed as scale, then shear along x, then rotation, then translation. A mirrored transform
+/// has a negative `scale_y`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decomposed {
+    pub translate_x: f32,
+    pub translate_y: f32,
+    /// Radians, clockwise on screen since y points down.
+    pub rotation: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    /// Shear factor along x.
+    pub shear: f32,
+}
+
+impl Affine2 {
+    pub const IDENTITY: Affine2 = Affine2::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+    pub const fn new(a: f32, b: f32, c: f32, d: f32, tx: f32, ty: f32) -> Affine2 {
+        Affine2 { a, b, c, d, tx, ty }
+    }
+
+    pub fn translation(x: f32, y: f32) -> Affine2 {
+        Affine2::new(1.0, 0.0, 0.0, 1.0, x, y)
+    }
+
+    pub fn scale(x: f32, y: f32) -> Affine2 {
+        Affine2::new(x, 0.0, 0.0, y, 0.0, 0.0)
+    }
+
+    pub fn rotation(radians: f32) -> Affine2 {
+        let (sin, cos) = radians.sin_cos();
+        Affine2::new(cos, -sin, sin, cos, 0.0, 0.0)
+    }
+
+    /// Applies `self` first and then `next`.
+    pub fn then(&self, next: &Affine2) -> Affine2 {
+        Affine2::new(
+            next.a * self.a + next.b * self.c,
+            next.a * self.b + next.b * self.d,
+            next.c * self.a + next.d * self.c,
+            next.c * self.b + next.d * self.d,
+            next.a * self.tx + next.b * self.ty + next.tx,
+            next.c * self.tx + next.d * self.ty + next.ty,
+        )
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.a * self.d - self.b * self.c
+    }
+
+    /// `None` when the transform collapses everything onto a line or point.
+    pub fn invert(&self) -> Option<Affine2> {
+        let det = self.determinant();
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+
+        Some(Affine2::new(
+            self.d / det,
+            -self.b / det,
+            -self.c / det,
+            self.a / det,
+            (self.b * self.ty - self.d * self.tx) / det,
+            (self.c * self.tx - self.a * self.ty) / det,
+        ))
+    }
+
+    pub fn transform_point(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            self.a * x + self.b * y + self.tx,
+            self.c * x + self.d * y + self.ty,
+        )
+    }
+
+    /// The corners of `rect` after transforming, clockwise from the top left of `rect`.
+    pub fn transform_rect(&self, rect: &Rect) -> [(f32, f32); 4] {
+        rect.corners().map(|corner| self.transform_point(corner))
+    }
+
+    /// Axis aligned bounds of `rect` after transforming.
+    pub fn transform_rect_bounds(&self, rect: &Rect) -> Rect {
+        let corners = self.transform_rect(rect);
+        let min_x = corners.iter().map(|c| c.0).fold(f32::MAX, f32::min);
+        let min_y = corners.iter().map(|c| c.1).fold(f32::MAX, f32::min);
+        let max_x = corners.iter().map(|c| c.0).fold(f32::MIN, f32::max);
+        let max_y = corners.iter().map(|c| c.1).fold(f32::MIN, f32::max);
+        Rect::new(min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+
+    pub fn decompose(&self) -> Decomposed {
+        let scale_x = self.a.hypot(self.c);
+        let rotation = self.c.atan2(self.a);
+        let (sin, cos) = rotation.sin_cos();
+
+        // Undo the rotation on the second column to get the shear and y scale
+        let shear_y = cos * self.b + sin * self.d;
+        let scale_y = cos * self.d - sin * self.b;
+        let shear = if scale_y.abs() < f32::EPSILON {
+            0.0
+        } else {
+            shear_y / scale_y
+        };
+
+        Decomposed {
+            translate_x: self.tx,
+            translate_y: self.ty,
+            rotation,
+            scale_x,
+            scale_y,
+            shear,
+        }
+    }
+
+    pub fn from_decomposed(parts: &Decomposed) -> Affine2 {
+        Affine2::scale(parts.scale_x, parts.scale_y)
+            .then(&Affine2::new(1.0, parts.shear, 0.0, 1.0, 0.0, 0.0))
+            .then(&Affine2::rotation(parts.rotation))
+            .then(&Affine2::translation(parts.translate_x, parts.translate_y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(left: Affine2, right: Affine2) {
+        let left = [left.a, left.b, left.c, left.d, left.tx, left.ty];
+        let right = [right.a, right.b, right.c, right.d, right.tx, right.ty];
+        for (l, r) in left.iter().zip(right.iter()) {
+            assert!((l - r).abs() < 1e-4, "{:?} != {:?}", left, right);
+        }
+    }
+
+    #[test]
+    fn then_applies_left_first() {
+        let scale_then_move = Affine2::scale(2.0, 3.0).then(&Affine2::translation(10.0, 20.0));
+
+        assert_eq!(scale_then_move.transform_point((1.0, 1.0)), (12.0, 23.0));
+    }
+
+    #[test]
+    fn invert_round_trips_points() {
+        let transform = Affine2::new(0.8, -0.3, 0.4, 1.2, 15.0, -7.0);
+        let inverse = transform.invert().unwrap();
+
+        assert_close(transform.then(&inverse), Affine2::IDENTITY);
+        let (x, y) = inverse.transform_point(transform.transform_point((3.0, 4.0)));
+        assert!((x - 3.0).abs() < 1e-4 && (y - 4.0).abs() < 1e-4);
+        assert!(Affine2::scale(0.0, 1.0).invert().is_none());
+    }
+
+    #[test]
+    fn decompose_round_trips() {
+        for transform in [
+            Affine2::IDENTITY,
+            Affine2::new(0.8, -0.3, 0.4, 1.2, 15.0, -7.0),
+            Affine2::new(-1.0, 0.0, 0.0, 1.0, 5.0, 5.0),
+            Affine2::rotation(1.0).then(&Affine2::scale(2.0, 0.5)),
+        ] {
+            assert_close(Affine2::from_decomposed(&transform.decompose()), transform);
+        }
+    }
+
+    #[test]
+    fn decompose_rotation_and_scale() {
+        let parts = Affine2::scale(2.0, 3.0)
+            .then(&Affine2::rotation(0.5))
+            .then(&Affine2::translation(4.0, 5.0))
+            .decompose();
+
+        assert!((parts.rotation - 0.5).abs() < 1e-5);
+        assert!((parts.scale_x - 2.0).abs() < 1e-5);
+        assert!((parts.scale_y - 3.0).abs() < 1e-5);
+        assert!(parts.shear.abs() < 1e-5);
+        assert_eq!((parts.translate_x, parts.translate_y), (4.0, 5.0));
+    }
+
+    #[test]
+    fn rect_bounds_of_rotated_square() {
+        let bounds = Affine2::rotation(std::f32::consts::FRAC_PI_4)
+            .transform_rect_bounds(&Rect::new(0.0, 0.0, 1.0, 1.0));
+
+        assert!((bounds.width - 2.0_f32.sqrt()).abs() < 1e-5);
+        assert!((bounds.x + 0.5_f32.sqrt()).abs() < 1e-5);
+    }
+}
@@ -1,4 +1,16 @@
+pub mod atlas;
+pub mod bounds;
 pub mod bsv3;
+pub mod building;
+pub mod draw_list;
 pub mod file_buffer;
+pub mod frame_rate;
+pub mod geometry;
 pub mod num;
+pub mod optimize;
+pub mod overlay;
+pub mod render;
 pub mod rgb;
+pub mod sprite_filter;
+pub mod tween;
+pub mod write;
@@ -15,3 +15,31 @@ pub fn lcm(nums: &[usize]) -> usize {
         a * b / gcd(a, b)
     }
 }
+
+/// Like [`lcm`] but `None` for an empty slice, a zero length or on overflow.
+pub fn checked_lcm(nums: &[usize]) -> Option<usize> {
+    nums.iter()
+        .try_fold(1_usize, |acc, &n| {
+            if n == 0 {
+                None
+            } else {
+                (acc / gcd(acc, n)).checked_mul(n)
+            }
+        })
+        .filter(|_| !nums.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_lcm_of_animation_lengths() {
+        assert_eq!(checked_lcm(&[4]), Some(4));
+        assert_eq!(checked_lcm(&[4, 6]), Some(12));
+        assert_eq!(checked_lcm(&[3, 5, 7]), Some(105));
+        assert_eq!(checked_lcm(&[]), None);
+        assert_eq!(checked_lcm(&[0, 3]), None);
+        assert_eq!(checked_lcm(&[usize::MAX, usize::MAX - 1]), None);
+    }
+}
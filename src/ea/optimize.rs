@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::ea::bsv3::{Frame, FrameGroup, Layer, BSV3};
+
+/// Entry counts before and after [`BSV3::optimize`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Savings {
+    pub frames: (usize, usize),
+    pub groups: (usize, usize),
+    pub sprites: (usize, usize),
+}
+
+/// Everything the file stores for a layer, equal for byte-identical layers.
+type LayerKey = [u32; 8];
+
+fn layer_key(layer: &Layer) -> LayerKey {
+    [
+        layer.sprite as u32,
+        layer.x.to_bits(),
+        layer.y.to_bits(),
+        layer.scale_x.to_bits(),
+        layer.skew_v.to_bits(),
+        layer.skew_h.to_bits(),
+        layer.scale_y.to_bits(),
+        layer.alpha as u32,
+    ]
+}
+
+fn frame_key(frame: &Frame) -> (u8, Vec<LayerKey>) {
+    (frame.unknown, frame.layers.iter().map(layer_key).collect())
+}
+
+/// Gives each distinct key an id in the order they are first seen, keeping the first value.
+struct Interner<K, T> {
+    ids: HashMap<K, usize>,
+    values: Vec<T>,
+}
+
+impl<K: Eq + Hash, T> Interner<K, T> {
+    fn new() -> Interner<K, T> {
+        Interner {
+            ids: HashMap::new(),
+            values: vec![],
+        }
+    }
+
+    fn id(&mut self, key: K, value: impl FnOnce() -> T) -> usize {
+        *self.ids.entry(key).or_insert_with(|| {
+            self.values.push(value());
+            self.values.len() - 1
+        })
+    }
+}
+
+/// Lays `runs` out in one list so that each appears in it contiguously, reusing a run already in
+/// the list or overlapping either end of it. Longer runs are placed first so shorter ones can
+/// reuse them. Returns the list and where each run starts.
+pub fn pack_runs(runs: &[Vec<usize>]) -> (Vec<usize>, Vec<usize>) {
+    let mut by_length = (0..runs.len()).collect::<Vec<usize>>();
+    by_length.sort_by_key(|&index| std::cmp::Reverse(runs[index].len()));
+
+    let mut list: Vec<usize> = vec![];
+    let mut starts = vec![0; runs.len()];
+    for index in by_length {
+        let run = &runs[index];
+        if let Some(start) = list.windows(run.len()).position(|window| window == run) {
+            starts[index] = start;
+            continue;
+        }
+
+        let overlap = |before: &[usize], after: &[usize]| {
+            (1..run.len())
+                .rev()
+                .find(|&length| {
+                    length <= before.len()
+                        && length <= after.len()
+                        && before[before.len() - length..] == after[..length]
+                })
+                .unwrap_or(0)
+        };
+        let (at_end, at_start) = (overlap(&list, run), overlap(run, &list));
+        if at_start > at_end {
+            // Everything placed so far moves back by what goes in front
+            let added = run.len() - at_start;
+            for start in &mut starts {
+                *start += added;
+            }
+            starts[index] = 0;
+            list.splice(0..0, run[..added].iter().copied());
+        } else {
+            starts[index] = list.len() - at_end;
+            list.extend(&run[at_end..]);
+        }
+    }
+    (list, starts)
+}
+
+impl BSV3 {
+    /// Shrinks the file without changing what any animation draws: byte-identical frames, and
+    /// groups in 0x0303 files, are merged, whatever no animation reaches is dropped and sprites
+    /// no layer uses are removed.
+    ///
+    /// Animation ranges must stay contiguous, so steps only share entries where a whole run of
+    /// them repeats, and the original order is kept if packing the runs doesn't shorten it. Steps
+    /// past the end of the frames or groups, which draw nothing, become empty ones, and layers of
+    /// missing sprites keep pointing past the end of the sprites.
+    pub fn optimize(&mut self) -> Savings {
+        let before = (self.frames.len(), self.groups.len(), self.sprites.len());
+
+        let mut frames = Interner::new();
+        let mut runs = vec![];
+        let starts = if self.format == 0x0303 {
+            let mut groups = Interner::new();
+            for animation in &self.animations {
+                let mut run = vec![];
+                for frame_ref in animation.iter_frames() {
+                    let (unknown, members) = match self.groups.get(frame_ref.index()) {
+                        Some(group) => (
+                            group.unknown,
+                            group
+                                .frames
+                                .iter()
+                                .filter_map(|&index| self.frames.get(index))
+                                .map(|frame| frames.id(frame_key(frame), || frame.clone()))
+                                .collect::<Vec<usize>>(),
+                        ),
+                        None => (0, vec![]),
+                    };
+                    run.push(groups.id((unknown, members.clone()), || FrameGroup {
+                        frame_count: members.len() as u16,
+                        unknown,
+                        frames: members,
+                    }));
+                }
+                runs.push(run);
+            }
+
+            let (order, starts) = pack_runs(&runs);
+            if order.len() < self.groups.len() {
+                self.groups = order.iter().map(|&id| groups.values[id].clone()).collect();
+                self.frames = frames.values;
+                Some(starts)
+            } else {
+                // Keep the groups where they are, only merging the frames they show
+                let old_frames = &self.frames;
+                for group in &mut self.groups {
+                    group.frames = group
+                        .frames
+                        .iter()
+                        .filter_map(|&index| old_frames.get(index))
+                        .map(|frame| frames.id(frame_key(frame), || frame.clone()))
+                        .collect();
+                    group.frame_count = group.frames.len() as u16;
+                }
+                self.frames = frames.values;
+                None
+            }
+        } else {
+            let empty = Frame {
+                layer_count: 0,
+                unknown: 0,
+                layers: vec![],
+            };
+            for animation in &self.animations {
+                let run = animation
+                    .iter_frames()
+                    .map(|frame_ref| {
+                        let frame = self.frames.get(frame_ref.index()).unwrap_or(&empty);
+                        frames.id(frame_key(frame), || frame.clone())
+                    })
+                    .collect::<Vec<usize>>();
+                runs.push(run);
+            }
+
+            let (order, starts) = pack_runs(&runs);
+            if order.len() < self.frames.len() {
+                self.frames = order.iter().map(|&id| frames.values[id].clone()).collect();
+                Some(starts)
+            } else {
+                None
+            }
+        };
+
+        if let Some(starts) = starts {
+            for ((animation, run), start) in self.animations.iter_mut().zip(&runs).zip(starts) {
+                animation.start = start as u16;
+                animation.end = (start + run.len() - 1) as u16;
+            }
+        }
+
+        self.remove_unused_sprites();
+
+        Savings {
+            frames: (before.0, self.frames.len()),
+            groups: (before.1, self.groups.len()),
+            sprites: (before.2, self.sprites.len()),
+        }
+    }
+
+    /// Drops sprites no layer uses and remaps the layers to the remaining ones.
+    pub fn remove_unused_sprites(&mut self) {
+        let mut used = vec![false; self.sprites.len()];
+        for layer in self.frames.iter().flat_map(|frame| &frame.layers) {
+            if let Some(used) = used.get_mut(layer.sprite as usize) {
+                *used = true;
+            }
+        }
+
+        let mut remap = vec![None; self.sprites.len()];
+        let mut sprites = vec![];
+        for (index, sprite) in self.sprites.iter().enumerate() {
+            if used[index] {
+                remap[index] = Some(sprites.len() as u16);
+                sprites.push(sprite.clone());
+            }
+        }
+
+        let missing = sprites.len() as u16;
+        for layer in self.frames.iter_mut().flat_map(|frame| &mut frame.layers) {
+            layer.sprite = remap
+                .get(layer.sprite as usize)
+                .copied()
+                .flatten()
+                .unwrap_or(missing);
+        }
+        self.sprites = sprites;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ea::bsv3::{Animation, AnimationKind, Sprite};
+    use crate::ea::draw_list::DrawCommand;
+
+    #[test]
+    fn packs_runs_reusing_repeats_and_overlaps() {
+        let runs = [vec![1, 2], vec![0, 1, 2], vec![2, 3], vec![0, 1, 2]];
+        assert_eq!(pack_runs(&runs), (vec![0, 1, 2, 3], vec![1, 0, 2, 0]));
+
+        // The second run only fits in front of the first
+        let runs = [vec![0, 1, 2], vec![3, 4, 0]];
+        assert_eq!(pack_runs(&runs), (vec![3, 4, 0, 1, 2], vec![2, 0]));
+    }
+
+    #[test]
+    fn keeps_frames_packing_would_not_shorten() {
+        let mut bsv3 = BSV3::new(String::from("building.bsv3"));
+        bsv3.parse().unwrap();
+        let frame = bsv3.frames[0].clone();
+        bsv3.frames = (0..5)
+            .map(|index| {
+                let mut frame = frame.clone();
+                frame.layers[0].x = index as f32;
+                frame
+            })
+            .collect();
+        for (animation, (start, end)) in bsv3.animations.iter_mut().zip([(2, 4), (0, 2)]) {
+            animation.start = start;
+            animation.end = end;
+        }
+        let layout = |bsv3: &BSV3| {
+            let frames = bsv3.frames.iter().map(frame_key).collect::<Vec<_>>();
+            let ranges = bsv3
+                .animations
+                .iter()
+                .map(|animation| (animation.start, animation.end))
+                .collect::<Vec<_>>();
+            (frames, ranges)
+        };
+        let before = layout(&bsv3);
+
+        // Packing the runs takes all five frames again
+        bsv3.optimize();
+        assert_eq!(layout(&bsv3), before);
+    }
+
+    #[test]
+    fn optimized_animations_draw_the_same() {
+        let mut bsv3 = BSV3::new(String::from("building.bsv3"));
+        bsv3.parse().unwrap();
+        // A copy of the first frame shown by its own animation, and a sprite nothing uses
+        bsv3.frames.push(bsv3.frames[0].clone());
+        bsv3.animations.push(Animation {
+            name: String::from("copy"),
+            start: bsv3.frames.len() as u16 - 1,
+            end: bsv3.frames.len() as u16 - 1,
+            kind: AnimationKind::Frames,
+        });
+        bsv3.sprites.insert(
+            0,
+            Sprite {
+                name: String::from("unused"),
+                x: 0,
+                y: 0,
+                width: 1,
+                height: 1,
+            },
+        );
+        for layer in bsv3.frames.iter_mut().flat_map(|frame| &mut frame.layers) {
+            layer.sprite += 1;
+        }
+
+        let drawn = |bsv3: &BSV3| {
+            bsv3.animations
+                .iter()
+                .flat_map(|animation| animation.iter_frames())
+                .map(|frame_ref| {
+                    bsv3.draw_list(frame_ref)
+                        .into_iter()
+                        .map(|command| DrawCommand {
+                            sprite: 0,
+                            ..command
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let before = drawn(&bsv3);
+
+        let savings = bsv3.optimize();
+        assert_eq!(drawn(&bsv3), before);
+        // Both animations of the sample already show the same frame
+        assert_eq!(savings.frames, (3, 1));
+        assert_eq!(savings.sprites, (3, 2));
+    }
+}
@@ -0,0 +1,251 @@
+use crate::ea::draw_list::DrawCommand;
+use crate::ea::geometry::Rect;
+
+/// Width of one isometric tile of the town grid, in asset pixels.
+pub const TILE_WIDTH: f32 = 32.0;
+/// Height of one isometric tile, half its width.
+pub const TILE_HEIGHT: f32 = TILE_WIDTH / 2.0;
+
+/// Half the length of the arms of the origin cross.
+const ORIGIN_SIZE: f32 = 8.0;
+/// Half the length of the arms of each layer's pivot cross.
+const PIVOT_SIZE: f32 = 3.0;
+
+const ORIGIN_COLOR: [u8; 4] = [255, 0, 0, 255];
+const BOUNDS_COLOR: [u8; 4] = [255, 255, 255, 255];
+const GRID_COLOR: [u8; 4] = [0, 0, 0, 96];
+
+/// Distinct colours for layer outlines, repeating after the last one.
+const LAYER_COLORS: [[u8; 4]; 8] = [
+    [255, 214, 0, 255],
+    [0, 200, 255, 255],
+    [255, 80, 200, 255],
+    [120, 255, 60, 255],
+    [255, 140, 0, 255],
+    [160, 110, 255, 255],
+    [0, 255, 180, 255],
+    [255, 255, 255, 255],
+];
+
+/// Debug geometry drawn over an asset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Overlays {
+    /// Cross at the asset origin, the point drawn at the canvas centre.
+    pub origin: bool,
+    /// Outline of each layer's transformed quad and a cross at its pivot, the sprite's top left.
+    pub layers: bool,
+    /// Union of the layer quads.
+    pub bounds: bool,
+    /// Isometric tile grid through the origin.
+    pub tile_grid: bool,
+}
+
+/// A line in asset space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Line {
+    pub from: (f32, f32),
+    pub to: (f32, f32),
+    /// Straight RGBA.
+    pub color: [u8; 4],
+}
+
+impl Overlays {
+    /// Parses a comma separated list of `origin`, `layers`, `bounds`, `grid`, or `all` or `none`.
+    pub fn parse(list: &str) -> Option<Overlays> {
+        let mut overlays = Overlays::default();
+        for name in list.split(',') {
+            match name.trim() {
+                "none" => {}
+                "origin" => overlays.origin = true,
+                "layers" => overlays.layers = true,
+                "bounds" => overlays.bounds = true,
+                "grid" => overlays.tile_grid = true,
+                "all" => {
+                    overlays = Overlays {
+                        origin: true,
+                        layers: true,
+                        bounds: true,
+                        tile_grid: true,
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Some(overlays)
+    }
+
+    pub fn any(&self) -> bool {
+        self.origin || self.layers || self.bounds || self.tile_grid
+    }
+
+    /// Lines for the enabled overlays over `commands`, back to front. The tile grid covers `area`.
+    pub fn lines(&self, commands: &[DrawCommand], area: &Rect) -> Vec<Line> {
+        let mut lines = vec![];
+
+        if self.tile_grid {
+            lines.extend(tile_grid(area));
+        }
+
+        if self.layers {
+            for (index, command) in commands.iter().enumerate() {
+                let color = LAYER_COLORS[index % LAYER_COLORS.len()];
+                let corners = command.transform.transform_rect(&command.local_rect());
+                lines.extend(outline(corners, color));
+                lines.extend(cross(corners[0], PIVOT_SIZE, color));
+            }
+        }
+
+        if self.bounds {
+            let bounds = commands
+                .iter()
+                .map(|command| {
+                    command
+                        .transform
+                        .transform_rect_bounds(&command.local_rect())
+                })
+                .reduce(|bounds, quad| bounds.union(&quad));
+            if let Some(bounds) = bounds {
+                lines.extend(outline(bounds.corners(), BOUNDS_COLOR));
+            }
+        }
+
+        if self.origin {
+            lines.extend(cross((0.0, 0.0), ORIGIN_SIZE, ORIGIN_COLOR));
+        }
+
+        lines
+    }
+
+    /// Area the overlays draw outside of the asset itself, so exports can include the origin.
+    pub fn extent(&self) -> Option<Rect> {
+        self.origin.then(|| {
+            Rect::new(
+                -ORIGIN_SIZE,
+                -ORIGIN_SIZE,
+                ORIGIN_SIZE * 2.0,
+                ORIGIN_SIZE * 2.0,
+            )
+        })
+    }
+}
+
+impl std::fmt::Display for Overlays {
+    /// Written so [`Overlays::parse`] reads it back.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let names = [
+            (self.origin, "origin"),
+            (self.layers, "layers"),
+            (self.bounds, "bounds"),
+            (self.tile_grid, "grid"),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| *name)
+        .collect::<Vec<&str>>();
+
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join(","))
+        }
+    }
+}
+
+/// Closed outline through four corners.
+fn outline(corners: [(f32, f32); 4], color: [u8; 4]) -> Vec<Line> {
+    (0..4)
+        .map(|index| Line {
+            from: corners[index],
+            to: corners[(index + 1) % 4],
+            color,
+        })
+        .collect()
+}
+
+fn cross((x, y): (f32, f32), size: f32, color: [u8; 4]) -> [Line; 2] {
+    [
+        Line {
+            from: (x - size, y),
+            to: (x + size, y),
+            color,
+        },
+        Line {
+            from: (x, y - size),
+            to: (x, y + size),
+            color,
+        },
+    ]
+}
+
+/// Both diagonals of the isometric grid crossing `area`, one line through the origin each.
+fn tile_grid(area: &Rect) -> Vec<Line> {
+    let mut lines = vec![];
+    let slope = TILE_HEIGHT / TILE_WIDTH;
+
+    for direction in [slope, -slope] {
+        // Lines y = direction * x + k * TILE_HEIGHT, for every k whose line crosses the area
+        let intercepts = [
+            area.y - direction * area.x,
+            area.y - direction * area.right(),
+            area.bottom() - direction * area.x,
+            area.bottom() - direction * area.right(),
+        ];
+        let min = intercepts.iter().copied().fold(f32::MAX, f32::min);
+        let max = intercepts.iter().copied().fold(f32::MIN, f32::max);
+
+        let first = (min / TILE_HEIGHT).floor() as i64;
+        let last = (max / TILE_HEIGHT).ceil() as i64;
+        for k in first..=last {
+            let intercept = k as f32 * TILE_HEIGHT;
+            lines.push(Line {
+                from: (area.x, direction * area.x + intercept),
+                to: (area.right(), direction * area.right() + intercept),
+                color: GRID_COLOR,
+            });
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ea::geometry::Affine2;
+
+    #[test]
+    fn parses_overlay_lists() {
+        let overlays = Overlays::parse("origin, grid").unwrap();
+        assert!(overlays.origin && overlays.tile_grid);
+        assert!(!overlays.layers && !overlays.bounds);
+        assert!(Overlays::parse("all").unwrap().bounds);
+        assert_eq!(Overlays::parse("pivots"), None);
+
+        assert_eq!(Overlays::parse(&overlays.to_string()), Some(overlays));
+        assert_eq!(Overlays::parse("none"), Some(Overlays::default()));
+    }
+
+    #[test]
+    fn outlines_each_layer_and_their_union() {
+        let command = |x: f32| DrawCommand {
+            sprite: 0,
+            source: Rect::new(0.0, 0.0, 10.0, 10.0),
+            transform: Affine2::translation(x, 0.0),
+            alpha: 1.0,
+        };
+        let overlays = Overlays {
+            layers: true,
+            bounds: true,
+            ..Overlays::default()
+        };
+        let lines = overlays.lines(
+            &[command(0.0), command(20.0)],
+            &Rect::new(0.0, 0.0, 1.0, 1.0),
+        );
+
+        assert_eq!(lines.len(), 16);
+        assert_ne!(lines[0].color, lines[6].color);
+        assert_eq!(lines[12].from, (0.0, 0.0));
+        assert_eq!(lines[12].to, (30.0, 0.0));
+    }
+}
@@ -0,0 +1,205 @@
+use image::RgbaImage;
+
+use crate::ea::bsv3::{FrameRef, BSV3};
+use crate::ea::draw_list::DrawCommand;
+use crate::ea::geometry::{Affine2, Rect};
+use crate::ea::overlay::Line;
+use crate::ea::rgb::RGB;
+use crate::ea::sprite_filter::SpriteFilter;
+
+/// Canvas width, height and origin that exactly fit `bounds`, rounded out to whole pixels.
+pub fn canvas_for_bounds(bounds: &Rect) -> (u32, u32, (f32, f32)) {
+    let left = bounds.x.floor();
+    let top = bounds.y.floor();
+    let width = (bounds.right().ceil() - left).max(1.0) as u32;
+    let height = (bounds.bottom().ceil() - top).max(1.0) as u32;
+    (width, height, (-left, -top))
+}
+
+/// Converts a canvas the [`Renderer`] drew, whose colours are premultiplied by alpha, to the
+/// straight alpha PNG expects. Canvases flattened over an opaque background need not be.
+pub fn unpremultiply(canvas: &mut RgbaImage) {
+    for pixel in canvas.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        if alpha == 0 || alpha == 255 {
+            continue;
+        }
+        for channel in &mut pixel.0[..3] {
+            *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+        }
+    }
+}
+
+/// Software renderer for headless exports, mirroring what the viewer draws on the GPU.
+pub struct Renderer<'a> {
+    pub bsv3: &'a BSV3,
+    pub texture: &'a RGB,
+    /// Canvas position of the asset origin.
+    pub origin: (f32, f32),
+    /// Sprites to hide or tint, as chosen in the viewer.
+    pub filter: Option<&'a SpriteFilter>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(bsv3: &'a BSV3, texture: &'a RGB, origin: (f32, f32)) -> Renderer<'a> {
+        Renderer {
+            bsv3,
+            texture,
+            origin,
+            filter: None,
+        }
+    }
+
+    pub fn with_filter(mut self, filter: &'a SpriteFilter) -> Renderer<'a> {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn draw(&self, canvas: &mut RgbaImage, frame_ref: FrameRef) {
+        self.draw_commands(canvas, &self.bsv3.draw_list(frame_ref));
+    }
+
+    /// Draws `commands` in order through the sprite filter, e.g. a tweened draw list.
+    pub fn draw_commands(&self, canvas: &mut RgbaImage, commands: &[DrawCommand]) {
+        for command in commands {
+            match self.filter {
+                Some(filter) if !filter.is_visible(command.sprite) => {}
+                Some(filter) => self.draw_tinted(canvas, command, filter.tint(command.sprite)),
+                None => self.draw_command(canvas, command),
+            }
+        }
+    }
+
+    pub fn draw_command(&self, canvas: &mut RgbaImage, command: &DrawCommand) {
+        self.draw_tinted(canvas, command, [1.0; 3]);
+    }
+
+    /// Draws `command` with its colour multiplied by `tint`.
+    pub fn draw_tinted(&self, canvas: &mut RgbaImage, command: &DrawCommand, tint: [f32; 3]) {
+        let transform = command
+            .transform
+            .then(&Affine2::translation(self.origin.0, self.origin.1));
+        let Some(inverse) = transform.invert() else {
+            return;
+        };
+
+        let width = command.source.width;
+        let height = command.source.height;
+
+        let bounds = transform.transform_rect_bounds(&command.local_rect());
+        let min_x = bounds.x.floor().max(0.0) as u32;
+        let min_y = bounds.y.floor().max(0.0) as u32;
+        let max_x = (bounds.right().ceil() as i64).clamp(0, canvas.width() as i64) as u32;
+        let max_y = (bounds.bottom().ceil() as i64).clamp(0, canvas.height() as i64) as u32;
+
+        let alpha = command.alpha;
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                // Map the pixel centre back into sprite space
+                let (u, v) = inverse.transform_point((x as f32 + 0.5, y as f32 + 0.5));
+
+                if u < 0.0 || v < 0.0 || u >= width || v >= height {
+                    continue;
+                }
+
+                let mut source = self.sample(command.source.x + u, command.source.y + v);
+                for (channel, factor) in source.iter_mut().zip(tint) {
+                    *channel *= factor;
+                }
+                let pixel = canvas.get_pixel_mut(x, y);
+                let source_alpha = source[3] * alpha;
+                for (channel, value) in pixel.0.iter_mut().zip(source) {
+                    let destination = *channel as f32 / 255.0;
+                    let blended = value * alpha + destination * (1.0 - source_alpha);
+                    *channel = (blended.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+            }
+        }
+    }
+
+    /// One pixel wide line, stepped along its longer axis.
+    pub fn draw_line(&self, canvas: &mut RgbaImage, line: &Line) {
+        let from = (line.from.0 + self.origin.0, line.from.1 + self.origin.1);
+        let to = (line.to.0 + self.origin.0, line.to.1 + self.origin.1);
+        let steps = (to.0 - from.0)
+            .abs()
+            .max((to.1 - from.1).abs())
+            .ceil()
+            .max(1.0) as u32;
+
+        let alpha = line.color[3] as f32 / 255.0;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let x = (from.0 + (to.0 - from.0) * t).floor();
+            let y = (from.1 + (to.1 - from.1) * t).floor();
+            if x < 0.0 || y < 0.0 || x >= canvas.width() as f32 || y >= canvas.height() as f32 {
+                continue;
+            }
+
+            let pixel = canvas.get_pixel_mut(x as u32, y as u32);
+            for (index, channel) in pixel.0.iter_mut().enumerate() {
+                // Premultiply the straight line colour
+                let value = if index == 3 {
+                    alpha
+                } else {
+                    line.color[index] as f32 / 255.0 * alpha
+                };
+                let blended = value + *channel as f32 / 255.0 * (1.0 - alpha);
+                *channel = (blended.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+    }
+
+    /// Bilinear sample of the atlas at texel coordinates, as normalised RGBA.
+    fn sample(&self, x: f32, y: f32) -> [f32; 4] {
+        let x = x - 0.5;
+        let y = y - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+
+        let texels = [
+            (
+                self.texture.pixel(x0 as i32, y0 as i32),
+                (1.0 - fx) * (1.0 - fy),
+            ),
+            (
+                self.texture.pixel(x0 as i32 + 1, y0 as i32),
+                fx * (1.0 - fy),
+            ),
+            (
+                self.texture.pixel(x0 as i32, y0 as i32 + 1),
+                (1.0 - fx) * fy,
+            ),
+            (self.texture.pixel(x0 as i32 + 1, y0 as i32 + 1), fx * fy),
+        ];
+
+        let mut color = [0.0; 4];
+        for (texel, weight) in texels {
+            for (channel, value) in color.iter_mut().zip(texel) {
+                *channel += value as f32 / 255.0 * weight;
+            }
+        }
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_straight_alpha() {
+        // Half transparent red as the renderer blends it
+        let mut canvas = RgbaImage::from_pixel(1, 1, image::Rgba([64, 0, 0, 128]));
+        unpremultiply(&mut canvas);
+
+        let path = std::env::temp_dir().join("tsto_bsv3_straight_alpha.png");
+        canvas.save(&path).unwrap();
+        let saved = image::open(&path).unwrap().to_rgba8();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.get_pixel(0, 0).0, [128, 0, 0, 128]);
+    }
+}
@@ -1,3 +1,110 @@
+use colored::Colorize;
+
+use crate::ea::file_buffer::FileBuffer;
+use crate::ea::geometry::Rect;
+
+#[derive(Debug)]
+pub struct RGB {
+    pub file: FileBuffer,
+    /// Bytes before the format, kept for writing.
+    pub unknown: Vec<u8>,
+    pub format: u8,
+    pub width: u16,
+    pub height: u16,
+    /// Decoded pixels, always RGBA8888.
+    pub data: Vec<u8>,
+}
+
+impl RGB {
+    pub fn new(name: String) -> RGB {
+        Self::open(name).unwrap()
+    }
+
+    /// Like [`RGB::new`] but returns the error if the file can't be opened.
+    pub fn open(name: String) -> std::io::Result<RGB> {
+        let file = FileBuffer::open(name)?;
+
+        Ok(RGB {
+            file,
+            unknown: vec![],
+            format: 0,
+            width: 0,
+            height: 0,
+            data: vec![],
+        })
+    }
+
+    pub fn parse(&mut self) -> std::io::Result<()> {
+        let start_time = std::time::Instant::now();
+        println!("{} {}.rgb", "Parsing".blue(), self.file.name);
+
+        self.unknown = self.file.read_buffer(3)?;
+        self.format = self.file.read_uint_8()?;
+        println!("Format: {}", self.format);
+        self.width = self.file.read_uint_16()?;
+        println!("Width: {}", self.width);
+        self.height = self.file.read_uint_16()?;
+        println!("Height: {}", self.height);
+        let texture_data = self.file.read_remaining()?;
+        println!("Texture: {}x{}", self.width, self.height);
+
+        self.data = if self.format == 0x20 {
+            rgba4444_to_rgba8888(texture_data, false)
+        } else if self.format == 0x60 {
+            la88_to_rgba8888(texture_data, false)
+        } else {
+            texture_data
+        };
+
+        let end_time = std::time::Instant::now();
+        println!("{} {:?}", "Done in".green(), end_time - start_time);
+        Ok(())
+    }
+
+    /// Smallest rectangle inside `source` holding every pixel with non-zero alpha, relative to
+    /// the top left of `source`. `None` if the region is fully transparent.
+    pub fn opaque_rect(&self, source: &Rect) -> Option<Rect> {
+        let left = source.x as i32;
+        let top = source.y as i32;
+        let mut min = (i32::MAX, i32::MAX);
+        let mut max = (i32::MIN, i32::MIN);
+
+        for y in top..top + source.height as i32 {
+            for x in left..left + source.width as i32 {
+                if self.pixel(x, y)[3] > 0 {
+                    min = (min.0.min(x), min.1.min(y));
+                    max = (max.0.max(x), max.1.max(y));
+                }
+            }
+        }
+
+        if min.0 > max.0 {
+            return None;
+        }
+
+        Some(Rect::new(
+            (min.0 - left) as f32,
+            (min.1 - top) as f32,
+            (max.0 - min.0 + 1) as f32,
+            (max.1 - min.1 + 1) as f32,
+        ))
+    }
+
+    /// RGBA of the pixel at `x`, `y`, clamped to the texture edges.
+    pub fn pixel(&self, x: i32, y: i32) -> [u8; 4] {
+        if self.width == 0 || self.height == 0 {
+            return [0; 4];
+        }
+        let x = x.clamp(0, self.width as i32 - 1) as usize;
+        let y = y.clamp(0, self.height as i32 - 1) as usize;
+        let offset = (y * self.width as usize + x) * 4;
+        match self.data.get(offset..offset + 4) {
+            Some(pixel) => [pixel[0], pixel[1], pixel[2], pixel[3]],
+            None => [0; 4],
+        }
+    }
+}
+
 pub fn rgba4444_to_rgba8888(input: Vec<u8>, premultiplied: bool) -> Vec<u8> {
     let mut output = Vec::with_capacity(input.len() * 2); // Each RGBA4444 pixel becomes 4 bytes in RGBA8888
 
@@ -32,6 +139,17 @@ pub fn rgba4444_to_rgba8888(input: Vec<u8>, premultiplied: bool) -> Vec<u8> {
     output
 }
 
+/// Inverse of [`rgba4444_to_rgba8888`] for straight alpha, exact for pixels it decoded.
+pub fn rgba8888_to_rgba4444(input: &[u8]) -> Vec<u8> {
+    input
+        .chunks_exact(4)
+        .flat_map(|pixel| {
+            let [r, g, b, a] = [pixel[0], pixel[1], pixel[2], pixel[3]].map(|value| value >> 4);
+            [(b << 4) | a, (r << 4) | g]
+        })
+        .collect()
+}
+
 pub fn la88_to_rgba8888(input: Vec<u8>, premultiplied: bool) -> Vec<u8> {
     let mut output = Vec::with_capacity(input.len() * 2); // Each LA88 pixel becomes 4 bytes in RGBA8888
 
@@ -61,3 +179,11 @@ pub fn la88_to_rgba8888(input: Vec<u8>, premultiplied: bool) -> Vec<u8> {
 
     output
 }
+
+/// Inverse of [`la88_to_rgba8888`], taking the luminance from the red channel.
+pub fn rgba8888_to_la88(input: &[u8]) -> Vec<u8> {
+    input
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[3], pixel[0]])
+        .collect()
+}
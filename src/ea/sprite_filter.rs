@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+
+use crate::ea::bsv3::Sprite;
+
+/// Multiplies the colour of a highlighted sprite.
+pub const HIGHLIGHT_TINT: [f32; 3] = [1.0, 0.55, 0.15];
+
+/// Which sprites are drawn, by index into `BSV3::sprites`, applied to every frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpriteFilter {
+    pub hidden: BTreeSet<usize>,
+    /// When set, only this sprite is drawn.
+    pub solo: Option<usize>,
+    /// Drawn tinted with [`HIGHLIGHT_TINT`].
+    pub highlight: Option<usize>,
+}
+
+impl SpriteFilter {
+    pub fn is_visible(&self, sprite: usize) -> bool {
+        match self.solo {
+            Some(solo) => solo == sprite,
+            None => !self.hidden.contains(&sprite),
+        }
+    }
+
+    pub fn is_highlighted(&self, sprite: usize) -> bool {
+        self.highlight == Some(sprite)
+    }
+
+    /// Colour multiplier for `sprite`, white unless highlighted.
+    pub fn tint(&self, sprite: usize) -> [f32; 3] {
+        if self.is_highlighted(sprite) {
+            HIGHLIGHT_TINT
+        } else {
+            [1.0; 3]
+        }
+    }
+
+    pub fn toggle_hidden(&mut self, sprite: usize) {
+        if !self.hidden.remove(&sprite) {
+            self.hidden.insert(sprite);
+        }
+    }
+
+    pub fn toggle_solo(&mut self, sprite: usize) {
+        self.solo = if self.solo == Some(sprite) {
+            None
+        } else {
+            Some(sprite)
+        };
+    }
+
+    pub fn toggle_highlight(&mut self, sprite: usize) {
+        self.highlight = if self.highlight == Some(sprite) {
+            None
+        } else {
+            Some(sprite)
+        };
+    }
+
+    /// The same selection in the `new` sprites, matching them by name since a re-export may
+    /// reorder them.
+    pub fn remap(&self, old: &[Sprite], new: &[Sprite]) -> SpriteFilter {
+        let find = |index: usize| {
+            let name = &old.get(index)?.name;
+            new.iter().position(|sprite| &sprite.name == name)
+        };
+
+        SpriteFilter {
+            hidden: self
+                .hidden
+                .iter()
+                .filter_map(|&index| find(index))
+                .collect(),
+            solo: self.solo.and_then(find),
+            highlight: self.highlight.and_then(find),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solo_overrides_hidden() {
+        let mut filter = SpriteFilter::default();
+        filter.toggle_hidden(1);
+        assert!(filter.is_visible(0));
+        assert!(!filter.is_visible(1));
+
+        filter.toggle_solo(1);
+        assert!(!filter.is_visible(0));
+        assert!(filter.is_visible(1));
+
+        filter.toggle_solo(1);
+        filter.toggle_hidden(1);
+        assert_eq!(filter, SpriteFilter::default());
+    }
+
+    #[test]
+    fn remaps_by_sprite_name() {
+        let sprites = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| Sprite {
+                    name: name.to_string(),
+                    x: 0,
+                    y: 0,
+                    width: 1,
+                    height: 1,
+                })
+                .collect::<Vec<Sprite>>()
+        };
+        let old = sprites(&["arm", "leg", "head"]);
+        let new = sprites(&["head", "arm"]);
+        let mut filter = SpriteFilter::default();
+        filter.toggle_hidden(0);
+        filter.toggle_hidden(1);
+        filter.toggle_solo(2);
+        filter.toggle_highlight(1);
+
+        let remapped = filter.remap(&old, &new);
+
+        assert_eq!(remapped.hidden, BTreeSet::from([1]));
+        assert_eq!(remapped.solo, Some(0));
+        assert_eq!(remapped.highlight, None);
+    }
+}
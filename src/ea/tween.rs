@@ -0,0 +1,117 @@
+use std::f32::consts::{PI, TAU};
+
+use crate::ea::bsv3::{Animation, BSV3};
+use crate::ea::draw_list::DrawCommand;
+use crate::ea::geometry::{Affine2, Decomposed};
+
+/// Quads `t` of the way from `from` to `to`, for smooth playback between authored steps.
+///
+/// Layers are matched in order and only when both lists draw the same sprites in the same order.
+/// Otherwise the step is a hard cut and `from` is shown until `t` reaches 1.
+pub fn tween(from: &[DrawCommand], to: &[DrawCommand], t: f32) -> Vec<DrawCommand> {
+    let matching = from.len() == to.len()
+        && from
+            .iter()
+            .zip(to)
+            .all(|(from, to)| from.sprite == to.sprite);
+    if !matching || t <= 0.0 {
+        return from.to_vec();
+    }
+
+    from.iter()
+        .zip(to)
+        .map(|(from, to)| DrawCommand {
+            sprite: from.sprite,
+            source: from.source,
+            transform: tween_transform(&from.transform, &to.transform, t),
+            alpha: lerp(from.alpha, to.alpha, t),
+        })
+        .collect()
+}
+
+/// Interpolates the decomposed parts, turning the short way round.
+fn tween_transform(from: &Affine2, to: &Affine2, t: f32) -> Affine2 {
+    let (from, to) = (from.decompose(), to.decompose());
+    let turn = (to.rotation - from.rotation + PI).rem_euclid(TAU) - PI;
+
+    Affine2::from_decomposed(&Decomposed {
+        translate_x: lerp(from.translate_x, to.translate_x, t),
+        translate_y: lerp(from.translate_y, to.translate_y, t),
+        rotation: from.rotation + turn * t,
+        scale_x: lerp(from.scale_x, to.scale_x, t),
+        scale_y: lerp(from.scale_y, to.scale_y, t),
+        shear: lerp(from.shear, to.shear, t),
+    })
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+/// Output frames for one loop of `steps` authored at `step_rate` per second, played at `fps`.
+pub fn tweened_frame_count(steps: usize, step_rate: f64, fps: f64) -> usize {
+    ((steps as f64 * fps / step_rate).round() as usize).max(1)
+}
+
+impl BSV3 {
+    /// Quads for `animation` `t` of the way from `step` to the next one, looping from the last
+    /// step back to the first.
+    pub fn tweened_draw_list(
+        &self,
+        animation: &Animation,
+        step: usize,
+        t: f32,
+    ) -> Vec<DrawCommand> {
+        let from = self.draw_list(animation.frame_at(step));
+        if t <= 0.0 {
+            return from;
+        }
+        tween(&from, &self.draw_list(animation.frame_at(step + 1)), t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ea::geometry::Rect;
+
+    fn command(sprite: usize, transform: Affine2, alpha: f32) -> DrawCommand {
+        DrawCommand {
+            sprite,
+            source: Rect::new(0.0, 0.0, 10.0, 10.0),
+            transform,
+            alpha,
+        }
+    }
+
+    #[test]
+    fn tweens_matching_layers() {
+        let from = [command(0, Affine2::translation(0.0, 0.0), 0.0)];
+        let to = [command(0, Affine2::translation(10.0, -4.0), 1.0)];
+
+        let halfway = tween(&from, &to, 0.5);
+        assert_eq!(
+            halfway[0].transform.transform_point((0.0, 0.0)),
+            (5.0, -2.0)
+        );
+        assert_eq!(halfway[0].alpha, 0.5);
+    }
+
+    #[test]
+    fn rotates_the_short_way_round() {
+        let from = [command(0, Affine2::rotation(PI - 0.1), 1.0)];
+        let to = [command(0, Affine2::rotation(-PI + 0.1), 1.0)];
+
+        let rotation = tween(&from, &to, 0.5)[0].transform.decompose().rotation;
+        assert!((rotation.abs() - PI).abs() < 1e-4, "{}", rotation);
+    }
+
+    #[test]
+    fn cuts_when_layers_differ() {
+        let from = [command(0, Affine2::IDENTITY, 1.0)];
+        let to = [command(1, Affine2::translation(10.0, 0.0), 1.0)];
+
+        assert_eq!(tween(&from, &to, 0.9), from.to_vec());
+        assert_eq!(tweened_frame_count(5, 24.0, 60.0), 13);
+    }
+}
@@ -0,0 +1,220 @@
+use std::io::{Error, ErrorKind};
+
+use crate::ea::bsv3::{Layer, BSV3};
+use crate::ea::rgb::{rgba8888_to_la88, rgba8888_to_rgba4444, RGB};
+
+impl BSV3 {
+    /// The parsed file serialised back in its own format, byte for byte what was read if nothing
+    /// changed. 0x0104 can't be written since its frames aren't parsed.
+    pub fn to_bytes(&self) -> std::io::Result<Vec<u8>> {
+        if !matches!(self.format, 0x0103 | 0x0203 | 0x0303) {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("writing format {:#06x} is not supported", self.format),
+            ));
+        }
+
+        let mut out = Writer::default();
+        out.u16(self.format);
+        if self.format == 0x0303 {
+            out.f32(self.header_unknown);
+        }
+        out.u16(count(self.sprites.len(), "sprites")?);
+        out.u8(self.data_type);
+        if self.format == 0x0203 {
+            out.string_8(&self.image_name)?;
+        }
+
+        for sprite in &self.sprites {
+            out.string_8(&sprite.name)?;
+            out.u16(sprite.x);
+            out.u16(sprite.y);
+            out.u16(sprite.width);
+            out.u16(sprite.height);
+        }
+
+        if self.format == 0x0303 {
+            out.u16(count(self.groups.len(), "groups")?);
+            out.u16(count(self.frames.len(), "frames")?);
+            for (index, frame) in self.frames.iter().enumerate() {
+                let [layer] = frame.layers.as_slice() else {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "frame {} must have exactly one layer in format 0x0303",
+                            index
+                        ),
+                    ));
+                };
+                out.layer(layer, self.data_type);
+            }
+            for group in &self.groups {
+                out.u16(count(group.frames.len(), "frames in a group")?);
+                out.u8(group.unknown);
+                for &frame in &group.frames {
+                    out.u16(count(frame, "frame index")?);
+                }
+            }
+        } else {
+            out.u16(count(self.frames.len(), "frames")?);
+            for frame in &self.frames {
+                out.u16(count(frame.layers.len(), "layers")?);
+                out.u8(frame.unknown);
+                // Layers are kept back to front, the file lists them the other way round
+                for layer in frame.layers.iter().rev() {
+                    out.layer(layer, self.data_type);
+                }
+            }
+        }
+
+        out.u16(count(self.animations.len(), "animations")?);
+        for animation in &self.animations {
+            out.string_8(&animation.name)?;
+            out.u16(animation.start);
+            out.u16(animation.end);
+        }
+
+        Ok(out.bytes)
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes()?)
+    }
+}
+
+impl RGB {
+    /// Fails unless the pixel format is one [`RGB::to_bytes`] can encode, RGBA4444 (0x20) or
+    /// LA88 (0x60). Other formats are read as raw bytes and can't be written back safely.
+    pub fn check_writable(&self) -> std::io::Result<()> {
+        if matches!(self.format, 0x20 | 0x60) {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("writing pixel format {:#04x} is not supported", self.format),
+            ))
+        }
+    }
+
+    /// The texture encoded back in its own pixel format.
+    pub fn to_bytes(&self) -> std::io::Result<Vec<u8>> {
+        self.check_writable()?;
+
+        let mut out = Writer::default();
+        out.bytes.extend(&self.unknown);
+        out.u8(self.format);
+        out.u16(self.width);
+        out.u16(self.height);
+        if self.format == 0x20 {
+            out.bytes.extend(rgba8888_to_rgba4444(&self.data));
+        } else {
+            out.bytes.extend(rgba8888_to_la88(&self.data));
+        }
+        Ok(out.bytes)
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes()?)
+    }
+}
+
+/// `len` as a u16 count, or an error naming `what` overflowed.
+fn count(len: usize, what: &str) -> std::io::Result<u16> {
+    u16::try_from(len).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("too many {} to write: {}", what, len),
+        )
+    })
+}
+
+/// Little endian counterpart of the `FileBuffer` reads.
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    /// Length prefixed and NUL terminated, the terminator counted in the length.
+    fn string_8(&mut self, value: &str) -> std::io::Result<()> {
+        let length = u8::try_from(value.len() + 1)
+            .ok()
+            .filter(|&length| length <= 254)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("name too long to write: {}", value),
+                )
+            })?;
+        self.u8(length);
+        self.bytes.extend(value.as_bytes());
+        self.u8(0);
+        Ok(())
+    }
+
+    fn layer(&mut self, layer: &Layer, data_type: u8) {
+        self.u16(layer.sprite);
+        self.f32(layer.x);
+        self.f32(layer.y);
+        self.f32(layer.scale_x);
+        self.f32(layer.skew_v);
+        self.f32(layer.skew_h);
+        self.f32(layer.scale_y);
+        if data_type == 1 {
+            self.u8(layer.alpha);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_back_what_was_read() {
+        let mut bsv3 = BSV3::new(String::from("building.bsv3"));
+        bsv3.parse().unwrap();
+
+        assert_eq!(
+            bsv3.to_bytes().unwrap(),
+            std::fs::read("building.bsv3").unwrap()
+        );
+
+        let mut rgb = RGB::new(bsv3.texture_path());
+        rgb.parse().unwrap();
+        assert_eq!(
+            rgb.to_bytes().unwrap(),
+            std::fs::read("building.rgb").unwrap()
+        );
+    }
+
+    #[test]
+    fn writes_back_la88_and_refuses_unknown_formats() {
+        let mut bytes = vec![0, 0, 0, 0x60];
+        bytes.extend([2, 0, 1, 0]);
+        bytes.extend([0xff, 0x10, 0x80, 0x7f]);
+        let path = std::env::temp_dir().join("tsto_bsv3_la88.rgb");
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut rgb = RGB::new(path.to_string_lossy().to_string());
+        rgb.parse().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(rgb.data, [0x10, 0x10, 0x10, 0xff, 0x7f, 0x7f, 0x7f, 0x80]);
+        assert_eq!(rgb.to_bytes().unwrap(), bytes);
+
+        rgb.format = 0x00;
+        assert_eq!(rgb.to_bytes().unwrap_err().kind(), ErrorKind::Unsupported);
+    }
+}
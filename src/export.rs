@@ -0,0 +1,281 @@
+use colored::Colorize;
+use image::RgbaImage;
+use tsto_bsv3::ea::bsv3::BSV3;
+use tsto_bsv3::ea::draw_list::DrawCommand;
+use tsto_bsv3::ea::frame_rate::{self, parse_rate, DEFAULT_FRAME_RATE};
+use tsto_bsv3::ea::geometry::Rect;
+use tsto_bsv3::ea::num::checked_lcm;
+use tsto_bsv3::ea::overlay::Overlays;
+use tsto_bsv3::ea::render::{canvas_for_bounds, unpremultiply, Renderer};
+use tsto_bsv3::ea::rgb::RGB;
+use tsto_bsv3::ea::sprite_filter::SpriteFilter;
+use tsto_bsv3::ea::tween::tweened_frame_count;
+
+use crate::background::{flatten, Background};
+
+/// Default upper bound on frames written for one synchronised loop.
+pub const MAX_LOOP_FRAMES: usize = 1000;
+
+/// How frames are rendered by [`export_loop`].
+pub struct ExportOptions {
+    /// Upper bound on frames written, the loop won't be seamless past it.
+    pub max_frames: usize,
+    /// Frames are transparent without one.
+    pub background: Option<Background>,
+    /// Debug geometry drawn over each frame.
+    pub overlays: Overlays,
+    /// Sprites hidden, soloed or highlighted in the viewer.
+    pub sprite_filter: SpriteFilter,
+    /// Animation steps per second, reported with the frames and tweened from.
+    pub frame_rate: f64,
+    /// Writes this many frames per second, tweened between steps, instead of one per step.
+    pub tween_fps: Option<f64>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> ExportOptions {
+        ExportOptions {
+            max_frames: MAX_LOOP_FRAMES,
+            background: None,
+            overlays: Overlays::default(),
+            sprite_filter: SpriteFilter::default(),
+            frame_rate: DEFAULT_FRAME_RATE,
+            tween_fps: None,
+        }
+    }
+}
+
+/// `bsv3 export <file.bsv3> [--anim NAME]... [--out DIR] [--max-frames N] [--bg BACKGROUND]
+/// [--overlay LIST] [--rate N] [--fps N]`
+pub fn run(args: &[String]) {
+    let mut file_path = None;
+    let mut animation_names = vec![];
+    let mut out_dir = String::from("pngs");
+    let mut options = ExportOptions::default();
+    let mut frame_rate = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--anim" => animation_names.extend(args.next().cloned()),
+            "--out" => out_dir = args.next().cloned().unwrap_or(out_dir),
+            "--max-frames" => {
+                options.max_frames = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(options.max_frames)
+            }
+            "--bg" => match args.next().map(|value| (value, Background::parse(value))) {
+                Some((_, Some(parsed))) => options.background = Some(parsed),
+                Some((value, None)) => {
+                    println!("{} unknown background {}", "Warning:".yellow(), value)
+                }
+                None => {}
+            },
+            "--rate" => match args.next().map(|value| (value, parse_rate(value))) {
+                Some((_, Some(rate))) => frame_rate = Some(rate),
+                Some((value, _)) => {
+                    println!("{} invalid frame rate {}", "Warning:".yellow(), value)
+                }
+                None => {}
+            },
+            "--fps" => match args.next().map(|value| (value, parse_rate(value))) {
+                Some((_, Some(fps))) => options.tween_fps = Some(fps),
+                Some((value, _)) => {
+                    println!("{} invalid frame rate {}", "Warning:".yellow(), value)
+                }
+                None => {}
+            },
+            "--overlay" => match args.next().map(|value| (value, Overlays::parse(value))) {
+                Some((_, Some(parsed))) => options.overlays = parsed,
+                Some((value, None)) => {
+                    println!("{} unknown overlay in {}", "Warning:".yellow(), value)
+                }
+                None => {}
+            },
+            _ => file_path = Some(arg.clone()),
+        }
+    }
+
+    let Some(file_path) = file_path else {
+        println!(
+            "{} bsv3 export <file.bsv3> [--anim NAME]... [--out DIR] [--max-frames N] [--bg BACKGROUND] [--overlay origin,layers,bounds,grid] [--rate N] [--fps N]",
+            "Usage:".red()
+        );
+        return;
+    };
+
+    options.frame_rate = frame_rate.unwrap_or_else(|| frame_rate::frame_rate_for(&file_path));
+
+    let (bsv3, texture) = match open_asset(&file_path) {
+        Ok(asset) => asset,
+        Err(error) => {
+            println!("{} {}", "Error:".red(), error);
+            return;
+        }
+    };
+
+    let mut animations = vec![];
+    for name in &animation_names {
+        match bsv3.animations.iter().position(|anim| &anim.name == name) {
+            Some(index) => animations.push(index),
+            None => println!("{} unknown animation {}", "Warning:".yellow(), name),
+        }
+    }
+    if animations.is_empty() && !bsv3.animations.is_empty() {
+        animations.push(0);
+    }
+
+    let result = std::fs::create_dir_all(&out_dir)
+        .map_err(|error| format!("could not create {}: {}", out_dir, error))
+        .and_then(|_| export_loop(&bsv3, &texture, &animations, &out_dir, &options));
+    if let Err(error) = result {
+        println!("{} {}", "Error:".red(), error);
+    }
+}
+
+/// Opens and parses a BSV3 and its texture.
+pub fn open_asset(file_path: &str) -> Result<(BSV3, RGB), String> {
+    let mut bsv3 = BSV3::open(file_path.into())
+        .map_err(|error| format!("could not open {}: {}", file_path, error))?;
+    bsv3.parse()
+        .map_err(|error| format!("could not parse {}: {}", file_path, error))?;
+
+    let mut texture = RGB::open(bsv3.texture_path())
+        .map_err(|error| format!("could not open {}: {}", bsv3.texture_path(), error))?;
+    texture
+        .parse()
+        .map_err(|error| format!("could not parse {}: {}", bsv3.texture_path(), error))?;
+
+    Ok((bsv3, texture))
+}
+
+/// Renders one loop of `animations` composited together, i.e. the lcm of their lengths so the
+/// sequence repeats seamlessly, capped at `options.max_frames`. With `options.tween_fps` the loop
+/// is written at that rate with frames tweened between steps. Returns the number of frames
+/// written.
+pub fn export_loop(
+    bsv3: &BSV3,
+    texture: &RGB,
+    animations: &[usize],
+    out_dir: &str,
+    options: &ExportOptions,
+) -> Result<usize, String> {
+    let max_frames = options.max_frames;
+    if animations.is_empty() {
+        return Err(String::from("no animations to export"));
+    }
+    let lengths = animations
+        .iter()
+        .map(|&id| bsv3.animations[id].frame_count())
+        .collect::<Vec<usize>>();
+    if let Some(index) = lengths.iter().position(|&length| length == 0) {
+        return Err(format!(
+            "{} has no frames",
+            bsv3.animations[animations[index]].name
+        ));
+    }
+
+    let loop_frames = checked_lcm(&lengths).map(|steps| match options.tween_fps {
+        Some(fps) => tweened_frame_count(steps, options.frame_rate, fps),
+        None => steps,
+    });
+    let frame_count = match loop_frames {
+        Some(frames) if frames <= max_frames => frames,
+        frames => {
+            // No lcm only when it overflows, the lengths being non-zero
+            let length = frames.map_or(String::from("too many"), |frames| frames.to_string());
+            println!(
+                "{} loop of {} frames ({:?}) exceeds {}, exporting {} frames which will not loop seamlessly",
+                "Warning:".yellow(),
+                length,
+                lengths,
+                max_frames,
+                max_frames
+            );
+            max_frames
+        }
+    };
+
+    let start_time = std::time::Instant::now();
+    println!(
+        "{} {} frames at {} fps",
+        "Exporting".blue(),
+        frame_count,
+        options.tween_fps.unwrap_or(options.frame_rate)
+    );
+
+    // Size the images to everything the animations draw
+    let extents = bsv3.sprite_extents(Some(texture));
+    let bounds = animations
+        .iter()
+        .filter_map(|&id| bsv3.animation_bounds(&bsv3.animations[id], &extents))
+        .chain(options.overlays.extent())
+        .reduce(|bounds, animation| bounds.union(&animation));
+    let (width, height, origin) = bounds.map_or((1, 1, (0.0, 0.0)), |b| canvas_for_bounds(&b));
+
+    let renderer = Renderer::new(bsv3, texture, origin).with_filter(&options.sprite_filter);
+
+    let background = match options
+        .background
+        .as_ref()
+        .map(|background| background.render(width, height))
+    {
+        Some(Err(error)) => return Err(format!("could not load background: {}", error)),
+        rendered => rendered.and_then(Result::ok),
+    };
+
+    // Steps advanced per frame, so the tweened loop ends exactly where it starts
+    let steps_per_frame = match (options.tween_fps, checked_lcm(&lengths), loop_frames) {
+        (Some(_), Some(steps), Some(frames)) => steps as f64 / frames as f64,
+        (Some(fps), ..) => options.frame_rate / fps,
+        (None, ..) => 1.0,
+    };
+
+    for tick in 0..frame_count {
+        let position = tick as f64 * steps_per_frame;
+        let (step, t) = (position.floor() as usize, position.fract() as f32);
+
+        // Animations showing the same thing are drawn once
+        let mut draw_lists: Vec<Vec<DrawCommand>> = vec![];
+        for &id in animations {
+            let commands = bsv3.tweened_draw_list(&bsv3.animations[id], step, t);
+            if !draw_lists.contains(&commands) {
+                draw_lists.push(commands);
+            }
+        }
+
+        let mut canvas = RgbaImage::new(width, height);
+        for commands in &draw_lists {
+            renderer.draw_commands(&mut canvas, commands);
+        }
+
+        if options.overlays.any() {
+            let commands = draw_lists
+                .iter()
+                .flatten()
+                .filter(|command| options.sprite_filter.is_visible(command.sprite))
+                .cloned()
+                .collect::<Vec<_>>();
+            let area = Rect::new(-origin.0, -origin.1, width as f32, height as f32);
+            for line in options.overlays.lines(&commands, &area) {
+                renderer.draw_line(&mut canvas, &line);
+            }
+        }
+
+        match &background {
+            Some(background) => flatten(&mut canvas, background),
+            None => unpremultiply(&mut canvas),
+        }
+
+        let png_path = format!("{}/{}_{:03}.png", out_dir, bsv3.file.name, tick);
+        canvas
+            .save(&png_path)
+            .map_err(|error| format!("could not save {}: {}", png_path, error))?;
+    }
+
+    let end_time = std::time::Instant::now();
+    println!("{} {:?}", "Done in".green(), end_time - start_time);
+
+    Ok(frame_count)
+}
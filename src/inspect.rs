@@ -0,0 +1,130 @@
+use image::RgbaImage;
+use tetra::graphics::mesh::{GeometryBuilder, ShapeStyle};
+use tetra::graphics::{Color, DrawParams, Rectangle};
+use tetra::math::Vec2;
+use tetra::Context;
+use tsto_bsv3::ea::render::Renderer;
+
+use crate::scene::Scene;
+
+/// Zoom from which the pixel grid is drawn.
+pub const GRID_MIN_SCALE: f32 = 8.0;
+
+/// Colour values under the cursor.
+pub struct PixelReadout {
+    /// Pixel in asset space.
+    pub point: (i32, i32),
+    /// The composited frame, as drawn on the canvas.
+    pub composite: [u8; 4],
+    /// The topmost sprite under the cursor and the atlas texel it samples.
+    pub atlas: Option<(String, (i32, i32), [u8; 4])>,
+}
+
+impl PixelReadout {
+    /// Reads the pixel at `point` of what `scene` drew last.
+    pub fn read(scene: &Scene, point: (f32, f32)) -> PixelReadout {
+        let pixel = (point.0.floor() as i32, point.1.floor() as i32);
+
+        // Render just this pixel with the software renderer, same geometry as the viewer
+        let mut canvas = RgbaImage::new(1, 1);
+        let renderer = Renderer::new(&scene.bsv3, &scene.rgb, (-pixel.0 as f32, -pixel.1 as f32))
+            .with_filter(&scene.sprite_filter);
+        renderer.draw_commands(&mut canvas, &scene.drawn);
+
+        let center = (pixel.0 as f32 + 0.5, pixel.1 as f32 + 0.5);
+        let atlas = scene
+            .visible_commands()
+            .into_iter()
+            .rfind(|command| command.hit_test(center))
+            .and_then(|command| {
+                let (u, v) = command.transform.invert()?.transform_point(center);
+                let texel = (
+                    (command.source.x + u).floor() as i32,
+                    (command.source.y + v).floor() as i32,
+                );
+                let name = scene.bsv3.sprites[command.sprite].name.clone();
+                Some((name, texel, scene.rgb.pixel(texel.0, texel.1)))
+            });
+
+        PixelReadout {
+            point: pixel,
+            composite: canvas.get_pixel(0, 0).0,
+            atlas,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = format!(
+            "Pixel ({}, {})\nFrame {}",
+            self.point.0,
+            self.point.1,
+            describe(self.composite)
+        );
+        if let Some((name, (x, y), rgba)) = &self.atlas {
+            text = format!(
+                "{}\nAtlas ({}, {}) {}\n      {}",
+                text,
+                x,
+                y,
+                name,
+                describe(*rgba)
+            );
+        }
+        text
+    }
+}
+
+/// Premultiplied values as stored, and the straight values they come from.
+fn describe(rgba: [u8; 4]) -> String {
+    let [r, g, b, a] = rgba;
+    let straight = |value: u8| {
+        if a == 0 {
+            0
+        } else {
+            (value as u32 * 255 / a as u32).min(255)
+        }
+    };
+    format!(
+        "premultiplied {:3} {:3} {:3} {:3} | straight {:3} {:3} {:3} {:3}",
+        r,
+        g,
+        b,
+        a,
+        straight(r),
+        straight(g),
+        straight(b),
+        a
+    )
+}
+
+/// Lines between canvas pixels, once they are big enough to see.
+pub fn draw_pixel_grid(ctx: &mut Context, scene: &Scene, view_size: Vec2<f32>) -> tetra::Result {
+    let scale = scene.scale.x;
+    if scale < GRID_MIN_SCALE {
+        return Ok(());
+    }
+
+    let origin = scene.canvas_position(view_size);
+    let first = Vec2::new(
+        origin.x + ((-origin.x) / scale).ceil() * scale,
+        origin.y + ((-origin.y) / scale).ceil() * scale,
+    );
+
+    let mut builder = GeometryBuilder::new();
+    builder.set_color(Color::rgba(0.0, 0.0, 0.0, 0.35));
+
+    let mut x = first.x;
+    while x < view_size.x {
+        builder.rectangle(ShapeStyle::Fill, Rectangle::new(x, 0.0, 1.0, view_size.y))?;
+        x += scale;
+    }
+    let mut y = first.y;
+    while y < view_size.y {
+        builder.rectangle(ShapeStyle::Fill, Rectangle::new(0.0, y, view_size.x, 1.0))?;
+        y += scale;
+    }
+
+    builder.build_mesh(ctx)?.draw(ctx, DrawParams::default());
+
+    Ok(())
+}
@@ -1,597 +1,867 @@
-#![allow(unused_variables)]
-#![allow(dead_code)]
-
-use crate::ea::bsv3::{Frame, Layer, BSV3};
-use crate::ea::file_buffer::FileBuffer;
-use crate::ea::rgb::{la88_to_rgba8888, rgba4444_to_rgba8888};
 use colored::Colorize;
-use delaunator::{triangulate, Point};
-use std::collections::HashMap;
-use tetra::graphics::mesh::{BufferUsage, IndexBuffer, Mesh, Vertex, VertexBuffer};
+use tetra::graphics::mesh::{Mesh, ShapeStyle};
 use tetra::graphics::text::{Font, Text};
 use tetra::graphics::{
-    self, BlendState, Canvas, Color, DrawParams, FilterMode, Texture, TextureFormat,
+    self, Canvas, Color, DrawParams, FilterMode, Rectangle, Texture, TextureFormat,
 };
-use tetra::math::num_traits::abs;
 use tetra::math::{Mat4, Vec2};
 use tetra::time::Timestep;
-use tetra::{input, time, Context, ContextBuilder, Event, State, TetraError};
-
-mod ea;
+use tetra::{input, time, window, Context, ContextBuilder, Event, State, TetraError};
+
+mod animation_list;
+mod background;
+mod bench;
+mod bindings;
+mod compare;
+mod export;
+mod inspect;
+mod optimize;
+mod recent_menu;
+mod recording;
+mod repack;
+mod scene;
+mod screenshot;
+mod settings;
+mod town;
+mod watcher;
+
+use crate::animation_list::AnimationList;
+use crate::background::Background;
+use crate::bindings::{Action, Bindings};
+use crate::compare::{CompareMode, Comparison};
+use crate::recent_menu::RecentMenu;
+use crate::recording::Recording;
+use crate::scene::{Scene, MAX_SCALE, MIN_SCALE};
+use crate::settings::Settings;
+use crate::town::Town;
+use tsto_bsv3::ea::frame_rate::{self, DEFAULT_FRAME_RATE};
+use tsto_bsv3::ea::overlay::Overlays;
 
 const WINDOW_WIDTH: f32 = 1024.0 + 256.0;
 const WINDOW_HEIGHT: f32 = 1024.0 + 256.0;
 const CANVAS_SIZE: f32 = 2048.0;
+/// Zoom factor per mouse wheel notch.
+const ZOOM_STEP: f32 = 1.2;
 const CANVAS_HALF: f32 = CANVAS_SIZE / 2.0;
-const DEBUG_LAYERS: bool = false;
-const SAVE_CANVAS: bool = false;
+/// Most steps shown either side of the current one when onion skinning.
+const MAX_ONION_SKIN: usize = 8;
+/// Where `R` writes recordings unless `--record-dir` is given.
+const DEFAULT_RECORD_DIR: &str = "recordings";
+/// Frame rates `Q` cycles an asset through, in animation steps per second.
+const FRAME_RATES: [f64; 4] = [12.0, 15.0, 24.0, 30.0];
+/// Display rates tweening cycles through, in frames per second.
+const TWEEN_RATES: [f64; 2] = [48.0, 60.0];
+/// Slowest and fastest playback speed, changed by doubling or halving.
+const MIN_PLAYBACK_SPEED: f64 = 0.125;
+const MAX_PLAYBACK_SPEED: f64 = 4.0;
 
 fn main() -> Result<(), TetraError> {
-    if SAVE_CANVAS {
-        std::fs::create_dir_all("pngs").unwrap();
-    }
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let view_args = match args.first().map(String::as_str) {
+        Some("export") => {
+            export::run(&args[1..]);
+            return Ok(());
+        }
+        Some("optimize") => {
+            optimize::run(&args[1..]);
+            return Ok(());
+        }
+        Some("repack") => {
+            repack::run(&args[1..]);
+            return Ok(());
+        }
+        Some("bench") => return bench::run(&args[1..]),
+        Some("view") => ViewArgs::parse(&args[1..]),
+        _ => ViewArgs::parse(&args),
+    };
+    let Some(view_args) = view_args else {
+        println!(
+            "{} bsv3 [view] [file.bsv3] [--anim NAME] [--scale N] [--bg grey|green|blue|checker|#RRGGBB|image.png] [--record-dir DIR] [--compare file.bsv3] [--rate N]",
+            "Usage:".red()
+        );
+        return Ok(());
+    };
+
+    let settings = Settings::load();
+    let (width, height) = settings
+        .window_size
+        .unwrap_or((WINDOW_WIDTH as i32, WINDOW_HEIGHT as i32));
 
-    ContextBuilder::new("TSTO BSV3", WINDOW_WIDTH as i32, WINDOW_HEIGHT as i32)
+    ContextBuilder::new("TSTO BSV3", width, height)
         .quit_on_escape(true)
         .show_mouse(true)
         .multisampling(16)
         .resizable(true)
-        .timestep(Timestep::Fixed(24.0))
+        .timestep(Timestep::Fixed(
+            DEFAULT_FRAME_RATE * settings.playback_speed,
+        ))
         .build()?
-        .run(GameState::new)
+        .run(|ctx| GameState::new(ctx, view_args, settings))
+}
+
+/// Current window size, which changes when the user resizes it.
+fn view_size(ctx: &Context) -> Vec2<f32> {
+    let (width, height) = window::get_size(ctx);
+    Vec2::new(width as f32, height as f32)
+}
+
+/// `dir` resolved against the working directory, so the viewer can show where files go.
+fn absolute_dir(dir: &str) -> String {
+    std::path::absolute(dir).map_or_else(|_| dir.to_string(), |path| path.display().to_string())
+}
+
+/// Options for `bsv3 view`.
+#[derive(Default)]
+struct ViewArgs {
+    file: Option<String>,
+    animation: Option<String>,
+    scale: Option<f32>,
+    background: Option<Background>,
+    record_dir: Option<String>,
+    compare: Option<String>,
+    /// Replaces the frame rate from the override file.
+    frame_rate: Option<f64>,
+}
+
+impl ViewArgs {
+    /// `None` if an option is unknown or missing its value.
+    fn parse(args: &[String]) -> Option<ViewArgs> {
+        let mut view_args = ViewArgs::default();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--anim" => view_args.animation = Some(args.next()?.clone()),
+                "--scale" => view_args.scale = Some(args.next()?.parse().ok()?),
+                "--bg" => view_args.background = Some(Background::parse(args.next()?)?),
+                "--record-dir" => view_args.record_dir = Some(args.next()?.clone()),
+                "--compare" => view_args.compare = Some(args.next()?.clone()),
+                "--rate" => view_args.frame_rate = Some(frame_rate::parse_rate(args.next()?)?),
+                _ if arg.starts_with("--") => return None,
+                _ => view_args.file = Some(arg.clone()),
+            }
+        }
+
+        Some(view_args)
+    }
 }
 
 struct GameState {
-    scene: Scene,
+    scene: Option<Scene>,
+    /// Second asset shown next to or over `scene`.
+    comparison: Option<Comparison>,
+    /// Assets placed on a grid, kept while switching back to `scene`.
+    town: Option<Town>,
+    /// Whether `town` is shown instead of `scene`.
+    town_mode: bool,
+    /// Shown instead of crashing when a file can't be loaded.
+    message: Option<String>,
+    /// Shown when something finished, like a recording.
+    notice: Option<String>,
+    background: Background,
+    /// `background` rendered at the view size, when it isn't a plain colour.
+    background_texture: Option<(Texture, Vec2<f32>)>,
+    /// Hex colour being typed after pressing `C`.
+    color_entry: Option<String>,
+    /// What was last saved, compared against the viewer state to save changes.
+    settings: Settings,
+    /// Multiplies the frame rate of every asset.
+    playback_speed: f64,
+    /// Display rate when interpolating between animation steps, `None` to show each step as is.
+    tween_fps: Option<f64>,
+    recent_menu: RecentMenu,
+    bindings: Bindings,
+    /// Lists every action and its binding.
+    show_help: bool,
     clip_canvas: Canvas,
-    clip_index: usize,
-    clip_id: u16,
-    clip_name: String,
     mouse_down: bool,
     font: Font,
+    /// Linear, or Nearest to see the atlas texels as they are.
+    filter_mode: FilterMode,
+    /// Shows the pixel grid and the colour under the cursor.
+    inspect: bool,
+    overlays: Overlays,
+    /// Steps drawn either side of the current one, 0 for off.
+    onion_skin: usize,
+    animation_list: AnimationList,
+    /// Folder recordings are written to.
+    record_dir: String,
+    recording: Option<Recording>,
 }
 
 impl GameState {
-    fn new(ctx: &mut Context) -> tetra::Result<GameState> {
-        let file_name = "./building.bsv3";
-
+    fn new(ctx: &mut Context, view_args: ViewArgs, settings: Settings) -> tetra::Result<GameState> {
         let font =
             Font::from_vector_file_data(ctx, include_bytes!("../UbuntuMono-Regular.ttf"), 18.0)
                 .ok()
                 .unwrap();
 
-        let scene = Scene::new(ctx, file_name, font.clone())?;
+        let filter_mode = if settings.nearest_filter {
+            FilterMode::Nearest
+        } else {
+            FilterMode::Linear
+        };
+        let mut clip_canvas = Canvas::new(ctx, CANVAS_SIZE as i32, CANVAS_SIZE as i32)?;
+        clip_canvas.set_filter_mode(ctx, filter_mode);
+
+        let (bindings, warnings) = Bindings::new(&settings.key_bindings);
+        for warning in &warnings {
+            println!("{} {}", "Warning:".yellow(), warning);
+        }
 
-        Ok(GameState {
-            scene,
-            clip_canvas: Canvas::new(ctx, CANVAS_SIZE as i32, CANVAS_SIZE as i32)?,
-            clip_index: 0,
-            clip_id: 0,
-            clip_name: String::from(""),
+        let mut state = GameState {
+            scene: None,
+            comparison: None,
+            town: None,
+            town_mode: false,
+            message: None,
+            notice: None,
+            background: view_args
+                .background
+                .or_else(|| settings.background.clone())
+                .unwrap_or_default(),
+            background_texture: None,
+            color_entry: None,
+            filter_mode,
+            overlays: settings.overlays,
+            playback_speed: settings.playback_speed,
+            tween_fps: None,
+            recent_menu: RecentMenu::default(),
+            bindings,
+            show_help: false,
+            settings,
+            clip_canvas,
             mouse_down: false,
             font,
-        })
-    }
+            inspect: false,
+            onion_skin: 0,
+            animation_list: AnimationList::default(),
+            record_dir: absolute_dir(
+                view_args
+                    .record_dir
+                    .as_deref()
+                    .unwrap_or(DEFAULT_RECORD_DIR),
+            ),
+            recording: None,
+        };
 
-    fn save_canvas(&mut self, ctx: &mut Context) {
-        let tex_data = self.clip_canvas.texture().get_data(ctx);
+        if let Some(file_path) = &view_args.file {
+            state.load(ctx, file_path);
+        }
+        if let Some(file_path) = &view_args.compare {
+            state.compare(ctx, file_path);
+        }
 
-        let index = self.scene.get_index();
-        let png_path = format!("pngs/{}_{:03}.png", self.scene.bsv3.file.name, index);
-        if std::path::Path::new(&png_path).exists() {
-            return;
+        if let Some(scene) = &mut state.scene {
+            if let Some(name) = &view_args.animation {
+                match scene
+                    .bsv3
+                    .animations
+                    .iter()
+                    .position(|anim| &anim.name == name)
+                {
+                    Some(index) => scene.animation = index,
+                    None => state.message = Some(format!("Unknown animation {}", name)),
+                }
+            }
+            if let Some(scale) = view_args.scale {
+                scene.scale = Vec2::broadcast(scale.clamp(MIN_SCALE, MAX_SCALE));
+            }
+            if let Some(frame_rate) = view_args.frame_rate {
+                scene.frame_rate = frame_rate;
+            }
         }
+        state.update_clock(ctx);
 
-        image::save_buffer_with_format(
-            png_path,
-            &tex_data.as_bytes(),
-            CANVAS_SIZE as u32,
-            CANVAS_SIZE as u32,
-            image::ColorType::Rgba8,
-            image::ImageFormat::Png,
-        )
-        .unwrap();
+        Ok(state)
     }
-}
 
-enum BGColor {
-    GREY,
-    GREEN,
-    BLUE,
-}
-
-struct Scene {
-    bg_color: BGColor,
-    bsv3: BSV3,
-    texture: Texture,
-    timer: usize,
-    scale: Vec2<f32>,
-    offset_x: f32,
-    offset_y: f32,
-    animation: usize,
-    always_draw_animations: Vec<usize>,
-    frames: HashMap<String, (Mesh, Mat4<f32>)>,
-    font: Font,
-}
-
-impl Scene {
-    fn new(ctx: &mut Context, file_path: &str, font: Font) -> Result<Scene, TetraError> {
-        if file_path.is_empty() {
-            return Ok(Scene {
-                bg_color: BGColor::GREY,
-                bsv3: BSV3::new(String::from("")),
-                texture: Texture::from_data(ctx, 0, 0, TextureFormat::R8, &*vec![])?,
-                timer: 0,
-                scale: Vec2::new(1.0, 1.0),
-                offset_x: 0.0,
-                offset_y: 400.0,
-                animation: 0,
-                always_draw_animations: vec![],
-                frames: HashMap::new(),
-                font,
-            });
-        }
-
-        let mut bsv3 = BSV3::new(String::from(file_path));
-
-        bsv3.parse();
-
-        // println!("Sprites: {:#?}", file.sprites);
-
-        let texture_file = format!("{}.rgb", bsv3.file.name);
-        let mut texture_path = format!("{}{}", bsv3.file.folder, texture_file);
-
-        if !bsv3.image_name.is_empty() {
-            texture_path = format!("{}{}", bsv3.file.folder, bsv3.image_name);
-        }
-
-        let start_time = std::time::Instant::now();
-        print!("{} {}\n", "Parsing".blue(), texture_file);
-        let mut texture_file = FileBuffer::new(texture_path);
-        texture_file.skip(3);
-        let format = texture_file.read_uint_8();
-        println!("Format: {}", format);
-        let width = texture_file.read_uint_16();
-        println!("Width: {}", width);
-        let height = texture_file.read_uint_16();
-        println!("Height: {}", height);
-        let texture_data = texture_file.read_remaining();
-        println!("Texture: {}x{}", width, height);
-
-        let texture_data_rgba8;
-
-        if format == 0x20 {
-            texture_data_rgba8 = rgba4444_to_rgba8888(texture_data, false);
-        } else if format == 0x60 {
-            texture_data_rgba8 = la88_to_rgba8888(texture_data, false);
-        } else {
-            texture_data_rgba8 = texture_data;
+    /// Replaces the scene with `file_path`, keeping the current one and showing why on failure.
+    fn load(&mut self, ctx: &mut Context, file_path: &str) {
+        if self.open(ctx, file_path) {
+            self.settings.add_recent_file(file_path);
+            self.settings.save();
+            self.recent_menu.cursor = 0;
         }
-
-        let mut texture = Texture::from_data(
-            ctx,
-            width as i32,
-            height as i32,
-            TextureFormat::Rgba8,
-            &*texture_data_rgba8,
-        )?;
-        texture.set_filter_mode(ctx, FilterMode::Linear);
-
-        let end_time = std::time::Instant::now();
-        print!("{} {:?}\n", "Done in".green(), end_time - start_time);
-
-        Ok(Scene {
-            bg_color: BGColor::GREY,
-            bsv3,
-            texture,
-            timer: 0,
-            scale: Vec2::new(1.0, 1.0),
-            offset_x: 0.0,
-            offset_y: 400.0,
-            animation: 0,
-            always_draw_animations: vec![],
-            frames: HashMap::new(),
-            font,
-        })
     }
 
-    pub fn transform_points(
-        &self,
-        destination_points: Vec<(f32, f32)>,
-        matrix: Vec<f64>,
-    ) -> Vec<Point> {
-        let mut points = vec![];
-
-        for point in destination_points {
-            let x = point.0 as f64;
-            let y = point.1 as f64;
-
-            // println!("Point: {:?}", point);
-            // println!("Matrix: {:?}", matrix);
-
-            let point = Point {
-                x: matrix[0] * x + matrix[1] * y + matrix[4],
-                y: matrix[2] * x + matrix[3] * y + matrix[5],
-            };
+    /// Opens the next recent file, leaving the list in order so repeated presses go through it.
+    fn open_next_recent(&mut self, ctx: &mut Context) {
+        let files = self.settings.recent_files.clone();
+        if files.is_empty() {
+            return;
+        }
 
-            // println!("Point: {:?}", point);
-            // println!("-----------------------------------------------");
+        self.recent_menu.cursor = (self.recent_menu.cursor + 1) % files.len();
+        self.open(ctx, &files[self.recent_menu.cursor]);
+    }
 
-            points.push(point);
+    /// Like [`GameState::load`] without adding to the recent files, returns whether it succeeded.
+    fn open(&mut self, ctx: &mut Context, file_path: &str) -> bool {
+        match Scene::new(ctx, file_path) {
+            Ok(mut scene) => {
+                let view_size = view_size(ctx);
+                scene.fit(view_size.x, view_size.y);
+                scene.set_filter_mode(ctx, self.filter_mode);
+                self.scene = Some(scene);
+                self.animation_list.reset_scroll();
+                self.message = None;
+                self.update_clock(ctx);
+                true
+            }
+            Err(error) => {
+                println!("{} {}", "Error:".red(), error);
+                self.message = Some(format!("Could not load {}\n{}", file_path, error));
+                false
+            }
         }
-
-        points
     }
 
-    pub fn canvas_to_mesh(&mut self, ctx: &mut Context, canvas: Canvas) -> Option<Mesh> {
-        let source_points: Vec<(f32, f32)> = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+    /// Opens `file_path` as the asset compared against the current one.
+    fn compare(&mut self, ctx: &mut Context, file_path: &str) {
+        if self.scene.is_none() {
+            self.message = Some(String::from("Open a file before comparing another with it"));
+            return;
+        }
 
-        let width = canvas.width() as f32;
-        let height = canvas.height() as f32;
+        match Scene::new(ctx, file_path)
+            .and_then(|compared| Comparison::new(ctx, compared, self.filter_mode))
+        {
+            Ok(comparison) => {
+                self.comparison = Some(comparison);
+                self.message = None;
+                let view_size = self.main_view_size(ctx);
+                if let Some(scene) = &mut self.scene {
+                    scene.fit(view_size.x, view_size.y);
+                }
+            }
+            Err(error) => {
+                println!("{} {}", "Error:".red(), error);
+                self.message = Some(format!("Could not load {}\n{}", file_path, error));
+            }
+        }
+    }
 
-        let destination_points: Vec<(f32, f32)> =
-            vec![(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)];
-        /*
-         * Generate Vertex Buffer
-         */
-        let mut vertices = vec![];
-        for index in 0..destination_points.len() {
-            vertices.push(Vertex::new(
-                Vec2::new(destination_points[index].0, destination_points[index].1),
-                Vec2::new(source_points[index].0, source_points[index].1),
-                Color::WHITE,
-            ));
-        }
-        let vertex_buffer = VertexBuffer::with_usage(ctx, &*vertices, BufferUsage::Static)
-            .ok()
-            .unwrap();
+    /// Switches between the town and the single asset, starting the town with the current asset.
+    fn toggle_town(&mut self, ctx: &mut Context) {
+        if self.town_mode {
+            self.town_mode = false;
+            return;
+        }
 
-        /*
-         * Create Mesh
-         */
-        let mut mesh = vertex_buffer.into_mesh();
+        if self.town.is_none() {
+            let Some(scene) = &self.scene else {
+                self.notice = Some(String::from("Open a file to start a town with"));
+                return;
+            };
+            let path = scene.path.clone();
+            match Scene::new(ctx, &path) {
+                Ok(mut building) => {
+                    building.keep_view(scene);
+                    building.set_filter_mode(ctx, self.filter_mode);
+                    let mut town = Town::new(building);
+                    town.set_filter_mode(ctx, self.filter_mode);
+                    let view_size = view_size(ctx);
+                    town.fit(view_size.x, view_size.y);
+                    self.town = Some(town);
+                }
+                Err(error) => {
+                    println!("{} {}", "Error:".red(), error);
+                    self.message = Some(format!("Could not load {}\n{}", path, error));
+                    return;
+                }
+            }
+        }
+        self.town_mode = true;
+    }
 
-        mesh.set_backface_culling(false);
+    /// Adds `file_path` to the town next to the other buildings.
+    fn add_to_town(&mut self, ctx: &mut Context, file_path: &str) {
+        let Some(town) = &mut self.town else {
+            return;
+        };
 
-        /*
-         * Generate Index Buffer
-         */
-        let mut indexes = vec![];
-        let result = triangulate(
-            &destination_points
-                .iter()
-                .map(|(x, y)| Point {
-                    x: *x as f64,
-                    y: *y as f64,
-                })
-                .collect::<Vec<Point>>(),
-        );
-        for index in result.triangles.iter() {
-            indexes.push(*index as u32);
+        match Scene::new(ctx, file_path) {
+            Ok(mut scene) => {
+                scene.set_filter_mode(ctx, self.filter_mode);
+                town.add(scene);
+                self.message = None;
+            }
+            Err(error) => {
+                println!("{} {}", "Error:".red(), error);
+                self.message = Some(format!("Could not load {}\n{}", file_path, error));
+            }
         }
+    }
 
-        let index_buffer = IndexBuffer::new(ctx, &*indexes).ok().unwrap();
+    /// Size of the part of the window showing the main scene, the left half when side by side.
+    fn main_view_size(&self, ctx: &Context) -> Vec2<f32> {
+        let view_size = view_size(ctx);
+        match &self.comparison {
+            Some(comparison) if comparison.mode == CompareMode::SideBySide => {
+                Vec2::new(view_size.x / 2.0, view_size.y)
+            }
+            _ => view_size,
+        }
+    }
 
-        mesh.set_index_buffer(index_buffer);
+    /// Mouse position relative to the view it is over, so both halves map to the same asset point.
+    fn mouse_in_main_view(&self, ctx: &Context) -> Vec2<f32> {
+        let mouse = input::get_mouse_position(ctx);
+        let main_view_size = self.main_view_size(ctx);
+        if mouse.x >= main_view_size.x {
+            Vec2::new(mouse.x - main_view_size.x, mouse.y)
+        } else {
+            mouse
+        }
+    }
 
-        let mut texture = canvas.texture().clone();
-        texture.set_filter_mode(ctx, FilterMode::Linear);
-        mesh.set_texture(texture);
+    /// Saves the settings when a remembered part of the viewer changed.
+    fn save_settings_if_changed(&mut self, ctx: &Context) {
+        let settings = Settings {
+            background: Some(self.background.clone()),
+            nearest_filter: self.filter_mode == FilterMode::Nearest,
+            playback_speed: self.playback_speed,
+            overlays: self.overlays,
+            window_size: Some(window::get_size(ctx)),
+            recent_files: self.settings.recent_files.clone(),
+            key_bindings: self.settings.key_bindings.clone(),
+        };
 
-        Option::from(mesh)
+        if settings != self.settings {
+            self.settings = settings;
+            self.settings.save();
+        }
     }
 
-    fn get_sprite_mesh(&mut self, ctx: &mut Context, layer: &Layer) -> Mesh {
-        let sprite = &self.bsv3.sprites[layer.sprite as usize];
+    /// Doubles or halves the playback speed.
+    fn change_playback_speed(&mut self, ctx: &mut Context, factor: f64) {
+        self.playback_speed =
+            (self.playback_speed * factor).clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+        self.update_clock(ctx);
+    }
 
-        let width = self.bsv3.sprites[layer.sprite as usize].width as f32;
-        let height = self.bsv3.sprites[layer.sprite as usize].height as f32;
+    /// Turns tweening off or steps through [`TWEEN_RATES`].
+    fn cycle_tween(&mut self, ctx: &mut Context) {
+        self.tween_fps = match self.tween_fps {
+            None => Some(TWEEN_RATES[0]),
+            Some(fps) => TWEEN_RATES.iter().copied().find(|&rate| rate > fps),
+        };
+        self.update_clock(ctx);
+    }
 
-        let points = [
-            Point { x: 0.0, y: 0.0 },
-            Point {
-                x: width as f64,
-                y: 0.0,
-            },
-            Point {
-                x: width as f64,
-                y: height as f64,
-            },
-            Point {
-                x: 0.0,
-                y: height as f64,
-            },
-        ];
-        /*
-         * Generate Vertex Buffer
-         */
-        let vertices = vec![
-            Vertex {
-                position: Vec2::new(0.0, 0.0),
-                uv: Vec2::new(
-                    sprite.x as f32 / self.texture.width() as f32,
-                    sprite.y as f32 / self.texture.height() as f32,
-                ),
-                color: Color::WHITE,
-            },
-            Vertex {
-                position: Vec2::new(width, 0.0),
-                uv: Vec2::new(
-                    (sprite.x + sprite.width) as f32 / self.texture.width() as f32,
-                    sprite.y as f32 / self.texture.height() as f32,
-                ),
-                color: Color::WHITE,
-            },
-            Vertex {
-                position: Vec2::new(width, height),
-                uv: Vec2::new(
-                    (sprite.x + sprite.width) as f32 / self.texture.width() as f32,
-                    (sprite.y + sprite.height) as f32 / self.texture.height() as f32,
-                ),
-                color: Color::WHITE,
-            },
-            Vertex {
-                position: Vec2::new(0.0, height),
-                uv: Vec2::new(
-                    sprite.x as f32 / self.texture.width() as f32,
-                    (sprite.y + sprite.height) as f32 / self.texture.height() as f32,
-                ),
-                color: Color::WHITE,
+    /// Moves the current asset, or the selected building in the town, to the next of
+    /// [`FRAME_RATES`].
+    fn cycle_frame_rate(&mut self, ctx: &mut Context) {
+        let scene = match (self.town_mode, &mut self.town, &mut self.scene) {
+            (true, Some(town), _) => match town.selected {
+                Some(index) => &mut town.buildings[index].scene,
+                None => return,
             },
-        ];
-        // println!("Vertices: {:#?}", vertices);
-        let vertex_buffer = VertexBuffer::with_usage(ctx, &*vertices, BufferUsage::Static)
-            .ok()
-            .unwrap();
-
-        /*
-         * Create Mesh
-         */
-        let mut mesh = vertex_buffer.into_mesh();
-
-        mesh.set_backface_culling(false);
-
-        /*
-         * Generate Index Buffer
-         */
-        let mut indexes = vec![];
-        let result = triangulate(&points);
-        for index in result.triangles.iter() {
-            indexes.push(*index as u32);
-        }
-
-        let index_buffer = IndexBuffer::new(ctx, &*indexes).ok().unwrap();
-
-        mesh.set_index_buffer(index_buffer);
+            (false, _, Some(scene)) => scene,
+            _ => return,
+        };
 
-        mesh.set_texture(self.texture.clone());
+        scene.frame_rate = FRAME_RATES
+            .iter()
+            .copied()
+            .find(|&rate| rate > scene.frame_rate)
+            .unwrap_or(FRAME_RATES[0]);
+        self.notice = Some(format!("Frame rate: {} fps", scene.frame_rate));
+        self.update_clock(ctx);
+    }
 
-        mesh
+    /// Steps per second of the current asset after the playback speed.
+    fn step_rate(&self) -> f64 {
+        let frame_rate = self
+            .scene
+            .as_ref()
+            .map_or(DEFAULT_FRAME_RATE, |scene| scene.frame_rate);
+        frame_rate * self.playback_speed
     }
 
-    fn draw_layer(&mut self, ctx: &mut Context, layer: &Layer) -> Option<(Mesh, Mat4<f32>)> {
-        let sprite = &self.bsv3.sprites[layer.sprite as usize];
-        let width = self.bsv3.sprites[layer.sprite as usize].width as f32;
-        let height = self.bsv3.sprites[layer.sprite as usize].height as f32;
+    /// Ticks per second: the tween rate when tweening, otherwise once per step of the current
+    /// asset.
+    fn tick_rate(&self) -> f64 {
+        self.tween_fps.unwrap_or_else(|| self.step_rate())
+    }
 
-        // let mut sprite_canvas = Canvas::new(ctx, width as i32, height as i32).unwrap();
-        // sprite_canvas.set_filter_mode(ctx, FilterMode::Linear);
-        // graphics::set_canvas(ctx, &sprite_canvas);
-        // graphics::clear(ctx, Color::rgba(0.0, 0.0, 0.0, 0.0));
+    fn update_clock(&self, ctx: &mut Context) {
+        time::set_timestep(ctx, Timestep::Fixed(self.tick_rate()));
+    }
 
-        let width = self.bsv3.sprites[layer.sprite as usize].width as f32;
-        let height = self.bsv3.sprites[layer.sprite as usize].height as f32;
+    /// Seconds of animation time that pass per tick, so an asset at `frame_rate` moves on
+    /// `frame_rate * seconds_per_tick` steps, a fraction when tweening or when its rate differs
+    /// from the current asset's.
+    fn seconds_per_tick(&self) -> f64 {
+        self.playback_speed / self.tick_rate()
+    }
 
-        // Setup offset fixes for positioning when scaled/skewed
-        let mut offset_x = 0.0;
-        let mut offset_y = 0.0;
+    /// Rebuilds the scenes from disk when their files changed, keeping the view where still valid.
+    fn reload_if_changed(&mut self, ctx: &mut Context) {
+        let compared = self
+            .comparison
+            .as_mut()
+            .map(|comparison| &mut comparison.scene);
+        let placed = self
+            .town
+            .iter_mut()
+            .flat_map(|town| town.buildings.iter_mut().map(|placed| &mut placed.scene));
+        let mut reloaded = false;
+        for scene in self.scene.iter_mut().chain(compared).chain(placed) {
+            if !scene.watcher.changed() {
+                continue;
+            }
 
-        if layer.scale_x < 0.0 {
-            offset_x += width * abs(layer.scale_x);
-        }
-        if layer.skew_h < 0.0 {
-            offset_x += height * abs(layer.skew_h);
+            println!("{} {}", "Reloading".blue(), scene.path);
+            match Scene::new(ctx, &scene.path) {
+                Ok(mut new_scene) => {
+                    new_scene.keep_view(scene);
+                    new_scene.set_filter_mode(ctx, self.filter_mode);
+                    *scene = new_scene;
+                    self.message = None;
+                    reloaded = true;
+                }
+                Err(error) => {
+                    println!("{} {}", "Error:".red(), error);
+                    self.message = Some(format!("Could not reload {}\n{}", scene.path, error));
+                }
+            }
         }
-        if layer.scale_y < 0.0 {
-            offset_y += height * abs(layer.scale_y);
+        // The reloaded asset may come with another frame rate
+        if reloaded {
+            self.update_clock(ctx);
         }
-        if layer.skew_v < 0.0 {
-            offset_y += width * abs(layer.skew_v);
+    }
+
+    /// Switches to `background`, remembered for the next run.
+    fn set_background(&mut self, background: Background) {
+        self.background = background;
+        self.background_texture = None;
+    }
+
+    /// Draws the background over the whole view, falling back to grey if its image can't be read.
+    fn draw_background(&mut self, ctx: &mut Context) -> tetra::Result {
+        if let Some(color) = self.background.color() {
+            graphics::clear(ctx, color);
+            return Ok(());
         }
 
-        let mesh = self.get_sprite_mesh(ctx, &layer);
+        let view_size = view_size(ctx);
+        if !matches!(&self.background_texture, Some((_, size)) if *size == view_size) {
+            match self
+                .background
+                .render(view_size.x as u32, view_size.y as u32)
+            {
+                Ok(image) => {
+                    let texture = Texture::from_data(
+                        ctx,
+                        image.width() as i32,
+                        image.height() as i32,
+                        TextureFormat::Rgba8,
+                        image.as_raw(),
+                    )?;
+                    self.background_texture = Some((texture, view_size));
+                }
+                Err(error) => {
+                    println!("{} {}", "Error:".red(), error);
+                    self.message = Some(format!("Could not load {}\n{}", self.background, error));
+                    self.background = Background::default();
+                    graphics::clear(ctx, Background::default().color().unwrap());
+                    return Ok(());
+                }
+            }
+        }
 
-        let transform = Mat4::new(
-            layer.scale_x,
-            layer.skew_h,
-            0.0,
-            layer.x + offset_x + CANVAS_HALF,
-            layer.skew_v,
-            layer.scale_y,
-            0.0,
-            layer.y + offset_y + CANVAS_HALF,
-            0.0,
-            0.0,
-            1.0,
-            0.0,
-            0.0,
-            0.0,
-            0.0,
-            1.0,
-        );
+        if let Some((texture, _)) = &self.background_texture {
+            texture.draw(ctx, Vec2::zero());
+        }
 
-        Option::from((mesh, transform))
+        Ok(())
     }
 
-    fn sprite_index_name(&self, layer: &Layer) -> String {
-        // sprite_id | x | y | scale_x | skew_h | skew_v | scale_y | alpha
-        format!(
-            "{}|{}|{}|{}|{}|{}|{}|{}",
-            layer.sprite,
-            layer.x,
-            layer.y,
-            layer.scale_x,
-            layer.skew_h,
-            layer.skew_v,
-            layer.scale_y,
-            layer.alpha
-        )
-    }
+    /// Handles typing a `#RRGGBB` background colour, returning whether the event was consumed.
+    fn enter_color(&mut self, ctx: &Context, event: &Event) -> bool {
+        let Some(entry) = &mut self.color_entry else {
+            return false;
+        };
 
-    fn get_index_for_animation(&self, animation_id: usize) -> usize {
-        let start = self.bsv3.animations[animation_id].start as usize;
-        let end = self.bsv3.animations[animation_id].end as usize;
-        let mut index = start;
-        if start != end {
-            index = start + self.timer % (end - start);
+        match event {
+            Event::TextInput { text } => entry.push_str(text),
+            Event::KeyPressed { key } => {
+                let shift = input::is_key_modifier_down(ctx, input::KeyModifier::Shift);
+                match self.bindings.key_action(*key, shift) {
+                    Some(Action::DeleteChar) => {
+                        entry.pop();
+                    }
+                    Some(Action::FinishTyping) => {
+                        let value = format!("#{}", entry.trim_start_matches('#'));
+                        self.color_entry = None;
+                        match Background::parse(&value) {
+                            Some(background) => self.set_background(background),
+                            None => self.message = Some(format!("Not a colour: {}", value)),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::KeyReleased { .. } => {}
+            _ => return false,
         }
 
-        index
+        true
     }
 
-    fn get_index(&self) -> usize {
-        self.get_index_for_animation(self.animation)
-    }
+    /// Starts recording one loop of what is shown, unless a recording is still running.
+    fn start_recording(&mut self) {
+        let Some(scene) = &self.scene else {
+            return;
+        };
+        if self.recording.is_some() || scene.bsv3.animations.is_empty() {
+            return;
+        }
 
-    fn draw_animation_group(&mut self, ctx: &mut Context, canvas: &Canvas, base_index: usize) {
-        if self.bsv3.format == 0x0303 {
-            // Collect reversed frame indices into a vector first (avoids borrowing)
-            let frames_to_draw = self.bsv3.groups[base_index]
-                .frames
-                .iter()
-                .rev() // Reverse the iterator
-                .copied()
-                .collect::<Vec<_>>();
+        println!("{} {}", "Recording to".blue(), self.record_dir);
+        self.notice = Some(format!("Recording to {}", self.record_dir));
+        self.recording = Some(Recording::start(scene, &self.record_dir, self.tween_fps));
+    }
 
-            for &frame_idx in &frames_to_draw {
-                self.draw_frame(
-                    ctx,
-                    canvas,
-                    self.bsv3.frames[frame_idx].clone(), // Or use frame directly if possible*
-                );
-            }
-        } else {
-            self.draw_frame(ctx, canvas, self.bsv3.frames[base_index].clone());
+    /// Saves the frame on screen cropped to its content, over the background if `with_background`.
+    fn save_screenshot(&mut self, with_background: bool) {
+        let Some(scene) = &self.scene else {
+            return;
+        };
+        if scene.drawn.is_empty() {
+            return;
         }
-    }
 
-    fn draw(&mut self, ctx: &mut Context) -> Canvas {
-        let canvas_width = CANVAS_SIZE;
-        let canvas_height = CANVAS_SIZE;
-        let mut canvas = Canvas::new(ctx, canvas_width as i32, canvas_height as i32).unwrap();
-        canvas.set_filter_mode(ctx, FilterMode::Linear);
-        graphics::set_canvas(ctx, &canvas);
-        match self.bg_color {
-            BGColor::GREY => {
-                graphics::clear(ctx, Color::rgba(0.5, 0.5, 0.5, 0.0));
+        let background = with_background.then_some(&self.background);
+        match screenshot::save(scene, &self.record_dir, background) {
+            Ok(path) => {
+                println!("{} {}", "Saved".green(), path);
+                self.notice = Some(format!("Saved {}", path));
             }
-            BGColor::GREEN => {
-                graphics::clear(ctx, Color::rgba(0.4, 0.7333, 0.4, 0.0));
-            }
-            BGColor::BLUE => {
-                graphics::clear(ctx, Color::rgba(0.0, 0.5333, 0.8, 0.0));
+            Err(error) => {
+                println!("{} {}", "Error:".red(), error);
+                self.message = Some(format!("Screenshot failed\n{}", error));
             }
         }
+    }
 
-        // Precompute extra animations' indices
-        let mut draw_indices: Vec<usize> = vec![self.get_index()];
-        for &animation_id in &self.always_draw_animations {
-            let idx = self.get_index_for_animation(animation_id);
-            draw_indices.push(idx);
+    /// Reports a recording that has finished.
+    fn poll_recording(&mut self) {
+        if !self.recording.as_ref().is_some_and(Recording::is_finished) {
+            return;
         }
+        let Some(recording) = self.recording.take() else {
+            return;
+        };
 
-        draw_indices.dedup();
+        let out_dir = recording.out_dir.clone();
+        match recording.finish() {
+            Ok(frames) => self.notice = Some(format!("Recorded {} frames to {}", frames, out_dir)),
+            Err(error) => {
+                println!("{} {}", "Error:".red(), error);
+                self.message = Some(format!("Recording failed\n{}", error));
+            }
+        }
+    }
 
-        // Iterate over precomputed indices (no borrow conflicts):
-        for &idx in &draw_indices {
-            self.draw_animation_group(ctx, &canvas, idx);
+    /// Every binding over a dark panel, when toggled on.
+    fn draw_help(&self, ctx: &mut Context) -> tetra::Result {
+        if !self.show_help {
+            return Ok(());
         }
 
-        self.timer += 1;
+        let mut text_help = Text::new(self.bindings.help_text(), self.font.clone());
+        let Some(bounds) = text_help.get_bounds(ctx) else {
+            return Ok(());
+        };
+        let panel = Rectangle::new(0.0, 0.0, bounds.width + 40.0, bounds.height + 40.0);
+        let position = (view_size(ctx) - Vec2::new(panel.width, panel.height)) / 2.0;
 
-        graphics::reset_canvas(ctx);
-        graphics::reset_blend_state(ctx);
+        Mesh::rectangle(ctx, ShapeStyle::Fill, panel)?.draw(
+            ctx,
+            DrawParams::default()
+                .position(position)
+                .color(Color::rgba(0.0, 0.0, 0.0, 0.8)),
+        );
+        text_help.draw(ctx, position + Vec2::new(20.0, 20.0));
+        Ok(())
+    }
 
-        canvas
+    /// Does what `action` is bound to.
+    fn perform(&mut self, ctx: &mut Context, action: Action) {
+        match action {
+            Action::Help => self.show_help = !self.show_help,
+            Action::Background => {
+                let background = self.background.next();
+                self.set_background(background);
+            }
+            Action::BackgroundColor => self.color_entry = Some(String::new()),
+            Action::NearestFilter => self.toggle_filter_mode(ctx),
+            Action::Inspect => self.inspect = !self.inspect,
+            Action::OverlayOrigin => self.overlays.origin = !self.overlays.origin,
+            Action::OverlayLayers => self.overlays.layers = !self.overlays.layers,
+            Action::OverlayBounds => self.overlays.bounds = !self.overlays.bounds,
+            Action::OverlayGrid => self.overlays.tile_grid = !self.overlays.tile_grid,
+            Action::FewerOnionFrames => self.onion_skin = self.onion_skin.saturating_sub(1),
+            Action::MoreOnionFrames => self.onion_skin = (self.onion_skin + 1).min(MAX_ONION_SKIN),
+            Action::Record => self.start_recording(),
+            Action::Screenshot => self.save_screenshot(false),
+            Action::ScreenshotWithBackground => self.save_screenshot(true),
+            Action::SlowerPlayback => self.change_playback_speed(ctx, 0.5),
+            Action::FasterPlayback => self.change_playback_speed(ctx, 2.0),
+            Action::FrameRate => self.cycle_frame_rate(ctx),
+            Action::RecentFiles => self.recent_menu.open = !self.recent_menu.open,
+            Action::NextRecentFile => self.open_next_recent(ctx),
+            Action::FilterAnimations => self.animation_list.start_filter(),
+            Action::CompareMode => match &mut self.comparison {
+                Some(comparison) => comparison.toggle_mode(),
+                None => {
+                    self.notice = Some(String::from(
+                        "Drop a second BSV3 with Shift held to compare it",
+                    ))
+                }
+            },
+            Action::CloseComparison => self.comparison = None,
+            Action::Town => self.toggle_town(ctx),
+            Action::Tween => self.cycle_tween(ctx),
+            _ if self.town_mode => self.perform_in_town(ctx, action),
+            _ => self.perform_on_scene(ctx, action),
+        }
     }
 
-    fn draw_frame(&mut self, ctx: &mut Context, canvas: &Canvas, frame: Frame) -> Option<()> {
-        let layers = frame.layers;
-        for layer in layers.iter() {
-            let layer_index = self.sprite_index_name(layer);
-            let (mesh, transform) = if let Some((mesh, transform)) = self.frames.get(&layer_index) {
-                (mesh.clone(), transform.clone())
-            } else {
-                match self.draw_layer(ctx, layer) {
-                    Some((layer_mesh, layer_transform)) => {
-                        self.frames
-                            .insert(layer_index, (layer_mesh.clone(), layer_transform.clone()));
-                        (layer_mesh, layer_transform)
+    /// Actions that act on the town's buildings instead of the single asset.
+    fn perform_in_town(&mut self, ctx: &mut Context, action: Action) {
+        let view_size = view_size(ctx);
+        let Some(town) = &mut self.town else {
+            return;
+        };
+
+        match action {
+            Action::RemoveBuilding => town.remove_selected(),
+            Action::Fit => town.fit(view_size.x, view_size.y),
+            Action::ActualSize => town.zoom_at(1.0 / town.scale.x, Vec2::zero()),
+            Action::CycleAnimation => {
+                let point = town.screen_to_town(input::get_mouse_position(ctx), view_size);
+                town.selected = town.building_at(point);
+                if let Some(index) = town.selected {
+                    let scene = &mut town.buildings[index].scene;
+                    if !scene.bsv3.animations.is_empty() {
+                        scene.animation = (scene.animation + 1) % scene.bsv3.animations.len();
                     }
-                    None => continue,
                 }
-            };
-
-            let offset_transform = transform
-                + Mat4::new(
-                    0.0,
-                    0.0,
-                    0.0,
-                    self.offset_x,
-                    0.0,
-                    0.0,
-                    0.0,
-                    self.offset_y,
-                    0.0,
-                    0.0,
-                    0.0,
-                    0.0,
-                    0.0,
-                    0.0,
-                    0.0,
-                    0.0,
-                );
+            }
+            _ => {}
+        }
+    }
 
-            let alpha = layer.alpha as f32 / 255.0;
+    /// Actions that need an open file.
+    fn perform_on_scene(&mut self, ctx: &mut Context, action: Action) {
+        let view_size = view_size(ctx);
+        let main_view_size = self.main_view_size(ctx);
+        let mouse = self.mouse_in_main_view(ctx);
+        let Some(scene) = &mut self.scene else {
+            return;
+        };
 
-            graphics::set_blend_state(ctx, BlendState::alpha(true));
+        match action {
+            Action::NextAnimation => self.animation_list.select_by(1, scene, view_size),
+            Action::PreviousAnimation => self.animation_list.select_by(-1, scene, view_size),
+            Action::CycleAnimation if !scene.bsv3.animations.is_empty() => {
+                scene.timer = 0;
+                scene.animation = (scene.animation + 1) % scene.bsv3.animations.len();
+                println!("Animation: {}", scene.animation);
+            }
+            Action::Digit(digit) => self.animation_list.type_digit(digit, scene, view_size),
+            Action::ScrollUp => self.animation_list.scroll_pages(-1, scene, view_size),
+            Action::ScrollDown => self.animation_list.scroll_pages(1, scene, view_size),
+            Action::Freeze => scene.toggle_frozen(scene.animation),
+            Action::Fit => scene.fit(main_view_size.x, main_view_size.y),
+            Action::ActualSize => scene.zoom_at(1.0 / scene.scale.x, Vec2::zero()),
+            Action::NextSprite | Action::PreviousSprite if !scene.bsv3.sprites.is_empty() => {
+                let count = scene.bsv3.sprites.len();
+                scene.selected_sprite = Some(match (scene.selected_sprite, action) {
+                    (None, Action::NextSprite) => 0,
+                    (None, _) => count - 1,
+                    (Some(index), Action::NextSprite) => (index + 1) % count,
+                    (Some(index), _) => (index + count - 1) % count,
+                });
+            }
+            Action::PickSprite => {
+                let point = scene.screen_to_asset(mouse, main_view_size);
+                scene.selected_sprite = scene.sprite_at(point);
+            }
+            Action::HideSprite | Action::SoloSprite | Action::HighlightSprite => {
+                if let Some(sprite) = scene.selected_sprite {
+                    match action {
+                        Action::HideSprite => scene.sprite_filter.toggle_hidden(sprite),
+                        Action::SoloSprite => scene.sprite_filter.toggle_solo(sprite),
+                        _ => scene.sprite_filter.toggle_highlight(sprite),
+                    }
+                }
+            }
+            Action::ShowAllSprites => scene.sprite_filter = Default::default(),
+            _ => {}
+        }
+    }
 
-            graphics::set_transform_matrix(ctx, offset_transform);
-            mesh.draw(
-                ctx,
-                DrawParams::default().color(Color::rgba(alpha, alpha, alpha, alpha)),
-            );
+    fn toggle_filter_mode(&mut self, ctx: &mut Context) {
+        self.filter_mode = match self.filter_mode {
+            FilterMode::Nearest => FilterMode::Linear,
+            _ => FilterMode::Nearest,
+        };
 
-            graphics::reset_transform_matrix(ctx);
+        self.clip_canvas.set_filter_mode(ctx, self.filter_mode);
+        if let Some(scene) = &mut self.scene {
+            scene.set_filter_mode(ctx, self.filter_mode);
+        }
+        if let Some(comparison) = &mut self.comparison {
+            comparison.set_filter_mode(ctx, self.filter_mode);
+        }
+        if let Some(town) = &mut self.town {
+            town.set_filter_mode(ctx, self.filter_mode);
         }
-
-        Some(())
     }
 }
 
 impl State for GameState {
     fn update(&mut self, ctx: &mut Context) -> tetra::Result {
-        if input::is_mouse_scrolled_up(ctx) {
-            self.scene.scale.x += 0.25;
-            self.scene.scale.y += 0.25;
-        } else if input::is_mouse_scrolled_down(ctx) {
-            self.scene.scale.x -= 0.25;
-            self.scene.scale.y -= 0.25;
+        self.reload_if_changed(ctx);
+        self.poll_recording();
+        self.save_settings_if_changed(ctx);
+
+        let seconds = self.seconds_per_tick();
+        let tween = self.tween_fps.is_some();
+        if let (true, Some(town)) = (self.town_mode, &mut self.town) {
+            let wheel = input::get_mouse_wheel_movement(ctx).y;
+            if wheel != 0 {
+                let anchor = input::get_mouse_position(ctx) - view_size(ctx) / 2.0;
+                town.zoom_at(ZOOM_STEP.powi(wheel), anchor);
+            }
+            return town.draw(ctx, seconds, tween);
         }
 
-        if self.scene.scale.x < 0.25 || self.scene.scale.y < 0.25 {
-            self.scene.scale.x = 0.25;
-            self.scene.scale.y = 0.25;
-        } else if self.scene.scale.x > 4.0 || self.scene.scale.y > 4.0 {
-            self.scene.scale.x = 4.0;
-            self.scene.scale.y = 4.0;
-        }
+        // Relative to the centre of the half the mouse is over when side by side
+        let anchor = self.mouse_in_main_view(ctx) - self.main_view_size(ctx) / 2.0;
+        let Some(scene) = &mut self.scene else {
+            return Ok(());
+        };
 
-        self.clip_canvas = self.scene.draw(ctx);
+        let wheel = input::get_mouse_wheel_movement(ctx).y;
+        let mouse = input::get_mouse_position(ctx);
+        if wheel != 0 && self.animation_list.contains(mouse, view_size(ctx)) {
+            self.animation_list.scroll_by(-wheel, scene, view_size(ctx));
+        } else if wheel != 0 {
+            scene.zoom_at(ZOOM_STEP.powi(wheel), anchor);
+        }
 
-        if SAVE_CANVAS {
-            self.save_canvas(ctx);
+        if let Some(comparison) = &mut self.comparison {
+            comparison.update(ctx, scene, self.onion_skin, tween);
         }
+        let steps = (scene.frame_rate * seconds) as f32;
+        scene.draw(ctx, &self.clip_canvas, self.onion_skin, steps, tween);
 
         Ok(())
     }
@@ -599,117 +869,254 @@ impl State for GameState {
     fn event(&mut self, ctx: &mut Context, event: Event) -> tetra::Result {
         // println!("{:?}", event);
 
-        if let Event::FileDropped { ref path } = event {
-            let mut file_path = path.to_str().unwrap().to_string();
-
-            if file_path.ends_with(".rgb") {
-                // check for bsv3 file existing with same name
-                let bsv3_file = file_path.replace(".rgb", ".bsv3");
-                let rgb_file = file_path.replace(".bsv3", ".rgb");
-                if !std::path::Path::new(&bsv3_file).exists() {
-                    return Ok(());
-                }
-
-                file_path = bsv3_file;
-            }
-
-            if !file_path.ends_with(".bsv3") {
-                return Ok(()); // Ignore non bsv3 files.
-            }
+        if self.enter_color(ctx, &event) {
+            return Ok(());
+        }
 
-            if !std::path::Path::new(&file_path.replace(".bsv3", ".rgb")).exists() {
+        if let Event::MouseButtonPressed { button } = event {
+            let position = input::get_mouse_position(ctx);
+            let files = &self.settings.recent_files;
+            if let (true, Some(index)) = (
+                self.bindings.is_click(button),
+                self.recent_menu.file_at(position, files, view_size(ctx)),
+            ) {
+                let file_path = files[index].clone();
+                self.load(ctx, &file_path);
                 return Ok(());
             }
-
-            self.scene = Scene::new(ctx, &file_path, self.font.clone())?;
         }
 
-        if let Event::MouseButtonPressed { button, .. } = event {
-            if button == input::MouseButton::Left {
-                self.mouse_down = true;
+        if let (false, Some(scene)) = (self.town_mode, &mut self.scene) {
+            if self
+                .animation_list
+                .event(ctx, &event, scene, &self.bindings, view_size(ctx))
+            {
+                return Ok(());
             }
         }
 
-        if let Event::MouseButtonReleased { button, .. } = event {
-            if button == input::MouseButton::Left {
-                self.mouse_down = false;
-            }
-            if button == input::MouseButton::Right {
-                self.scene.timer = 0;
-                self.scene.animation =
-                    (self.scene.animation + 1) % self.scene.bsv3.animations.len();
-                println!("Animation: {}", self.scene.animation);
-            }
-        }
+        match event {
+            Event::FileDropped { ref path } => {
+                let mut file_path = path.to_string_lossy().to_string();
+
+                if path.is_dir() {
+                    // Record and save screenshots into dropped folders
+                    self.record_dir = absolute_dir(&file_path);
+                    println!("{} {}", "Recording to".blue(), self.record_dir);
+                    self.notice = Some(format!(
+                        "Recordings and screenshots go to {}",
+                        self.record_dir
+                    ));
+                    return Ok(());
+                }
 
-        if let Event::MouseMoved { position, delta } = event {
-            if self.mouse_down {
-                self.scene.offset_x += delta.x * (1.0 / self.scene.scale.x);
-                self.scene.offset_y += delta.y * (1.0 / self.scene.scale.y);
-            }
-        }
+                if let Ok(image::ImageFormat::Png | image::ImageFormat::Jpeg) =
+                    image::ImageFormat::from_path(path)
+                {
+                    // Tile dropped images behind the asset
+                    self.set_background(Background::Image(file_path));
+                    return Ok(());
+                }
 
-        if let Event::KeyReleased { key, .. } = event {
-            match key {
-                input::Key::F => {
-                    // Freeze/Unfreeze animation
-                    if self
-                        .scene
-                        .always_draw_animations
-                        .contains(&self.scene.animation)
-                    {
-                        self.scene
-                            .always_draw_animations
-                            .retain(|&x| x != self.scene.animation);
-                    } else {
-                        self.scene.always_draw_animations.push(self.scene.animation);
-                    }
+                if file_path.ends_with(".rgb") {
+                    // Load the bsv3 file with the same name
+                    file_path = file_path.replace(".rgb", ".bsv3");
                 }
-                input::Key::B => match self.scene.bg_color {
-                    BGColor::GREY => {
-                        self.scene.bg_color = BGColor::GREEN;
-                    }
-                    BGColor::GREEN => {
-                        self.scene.bg_color = BGColor::BLUE;
-                    }
-                    BGColor::BLUE => {
-                        self.scene.bg_color = BGColor::GREY;
+
+                if !file_path.ends_with(".bsv3") {
+                    return Ok(()); // Ignore non bsv3 files.
+                }
+
+                if self.town_mode {
+                    self.add_to_town(ctx, &file_path);
+                } else if input::is_key_modifier_down(ctx, input::KeyModifier::Shift) {
+                    self.compare(ctx, &file_path);
+                } else {
+                    self.load(ctx, &file_path);
+                }
+            }
+            Event::MouseButtonPressed { button }
+                if self.bindings.mouse_action(button) == Some(Action::Pan) =>
+            {
+                // Buildings in the town are dragged instead of panning
+                let point = input::get_mouse_position(ctx);
+                let grabbed = match (self.town_mode, &mut self.town) {
+                    (true, Some(town)) => town.grab(town.screen_to_town(point, view_size(ctx))),
+                    _ => false,
+                };
+                self.mouse_down = !grabbed;
+            }
+            Event::MouseButtonReleased { button } => match self.bindings.mouse_action(button) {
+                Some(Action::Pan) => {
+                    self.mouse_down = false;
+                    if let Some(town) = &mut self.town {
+                        town.release();
                     }
-                },
-                _ => {}
+                }
+                Some(action) => self.perform(ctx, action),
+                None => {}
+            },
+            Event::MouseMoved { delta, position } if self.town_mode => {
+                let Some(town) = &mut self.town else {
+                    return Ok(());
+                };
+                if !town.drag_to(town.screen_to_town(position, view_size(ctx))) && self.mouse_down {
+                    town.offset_x += delta.x * (1.0 / town.scale.x);
+                    town.offset_y += delta.y * (1.0 / town.scale.y);
+                }
             }
+            Event::MouseMoved { delta, .. } => {
+                if let (true, Some(scene)) = (self.mouse_down, &mut self.scene) {
+                    scene.offset_x += delta.x * (1.0 / scene.scale.x);
+                    scene.offset_y += delta.y * (1.0 / scene.scale.y);
+                }
+            }
+            Event::KeyReleased { key } => {
+                let shift = input::is_key_modifier_down(ctx, input::KeyModifier::Shift);
+                if let Some(action) = self.bindings.key_action(key, shift) {
+                    self.perform(ctx, action);
+                }
+            }
+            _ => {}
         }
 
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> tetra::Result {
-        match self.scene.bg_color {
-            BGColor::GREY => {
-                graphics::clear(ctx, Color::rgb(0.5, 0.5, 0.5));
+        match &self.comparison {
+            Some(comparison) if comparison.mode == CompareMode::Difference => {
+                graphics::clear(ctx, Color::BLACK)
+            }
+            _ => self.draw_background(ctx)?,
+        }
+
+        /*
+         * Draw Message
+         */
+        if let Some(message) = &self.message {
+            let mut text_message = Text::new(message, self.font.clone());
+            text_message.draw(
+                ctx,
+                DrawParams::default()
+                    .position(Vec2::new(10.0, view_size(ctx).y - 60.0))
+                    .color(Color::RED),
+            );
+        }
+        if let Some(notice) = &self.notice {
+            let mut text_notice = Text::new(notice, self.font.clone());
+            text_notice.draw(ctx, Vec2::new(10.0, view_size(ctx).y - 80.0));
+        }
+
+        /*
+         * Draw Recent Files
+         */
+        self.recent_menu
+            .draw(ctx, &self.font, &self.settings.recent_files, view_size(ctx));
+
+        /*
+         * Draw Recording Indicator
+         */
+        if let Some(recording) = &self.recording {
+            let mut text_recording = Text::new(
+                format!(
+                    "REC {} frames to {}",
+                    recording.frame_count, recording.out_dir
+                ),
+                self.font.clone(),
+            );
+            let width = text_recording
+                .get_bounds(ctx)
+                .map_or(0.0, |bounds| bounds.width);
+            text_recording.draw(
+                ctx,
+                DrawParams::default()
+                    .position(Vec2::new(view_size(ctx).x - width - 10.0, 10.0))
+                    .color(Color::RED),
+            );
+        }
+
+        if let Some(entry) = &self.color_entry {
+            let mut text_entry = Text::new(
+                format!("Background colour: #{}_ (Enter to apply)", entry),
+                self.font.clone(),
+            );
+            text_entry.draw(ctx, Vec2::new(10.0, view_size(ctx).y - 30.0));
+        }
+
+        if let (true, Some(town)) = (self.town_mode, &self.town) {
+            town.draw_canvas(ctx, view_size(ctx));
+            town.draw_footprints(ctx, &self.overlays, view_size(ctx))?;
+
+            let mut text_town = Text::new(town.status(), self.font.clone());
+            text_town.draw(ctx, Vec2::new(10.0, 10.0));
+            return self.draw_help(ctx);
+        }
+
+        let Some(scene) = &self.scene else {
+            let mut text_hint = Text::new(
+                "Drag & drop a BSV3/RGB file onto the window (H for help)",
+                self.font.clone(),
+            );
+            text_hint.draw(ctx, Vec2::new(10.0, 10.0));
+            return self.draw_help(ctx);
+        };
+
+        let main_view_size = self.main_view_size(ctx);
+        let canvas_params = DrawParams::default()
+            .position(scene.canvas_position(main_view_size))
+            .scale(scene.scale);
+
+        match &self.comparison {
+            Some(comparison) if comparison.mode == CompareMode::Difference => {
+                comparison.draw_difference(ctx, &self.clip_canvas, canvas_params);
+                scene.draw_overlays(ctx, &self.overlays, main_view_size)?;
             }
-            BGColor::GREEN => {
-                graphics::clear(ctx, Color::rgb(0.4, 0.7333, 0.4));
+            Some(comparison) => {
+                // Each half clipped to itself, the right one shifted over
+                let (width, height) = window::get_physical_size(ctx);
+                graphics::set_scissor(ctx, Rectangle::new(0, 0, width / 2, height));
+                self.clip_canvas.draw(ctx, canvas_params.clone());
+                scene.draw_overlays(ctx, &self.overlays, main_view_size)?;
+
+                graphics::set_scissor(ctx, Rectangle::new(width / 2, 0, width / 2, height));
+                graphics::set_transform_matrix(
+                    ctx,
+                    Mat4::translation_2d(Vec2::new(main_view_size.x, 0.0)),
+                );
+                comparison.canvas().draw(ctx, canvas_params);
+                comparison
+                    .scene
+                    .draw_overlays(ctx, &self.overlays, main_view_size)?;
+                graphics::reset_transform_matrix(ctx);
+                graphics::reset_scissor(ctx);
+
+                Mesh::rectangle(
+                    ctx,
+                    ShapeStyle::Fill,
+                    Rectangle::new(main_view_size.x - 1.0, 0.0, 2.0, main_view_size.y),
+                )?
+                .draw(ctx, DrawParams::default().color(Color::BLACK));
             }
-            BGColor::BLUE => {
-                graphics::clear(ctx, Color::rgb(0.0, 0.5333, 0.8));
+            None => {
+                self.clip_canvas.draw(ctx, canvas_params);
+                scene.draw_overlays(ctx, &self.overlays, main_view_size)?;
             }
         }
 
-        self.clip_canvas.draw(
-            ctx,
-            DrawParams::default()
-                .position(Vec2::new(
-                    (WINDOW_WIDTH - CANVAS_SIZE * self.scene.scale.x) / 2.0,
-                    (WINDOW_HEIGHT - CANVAS_SIZE * self.scene.scale.y) / 2.0,
-                ))
-                .scale(self.scene.scale),
-        );
+        if self.inspect {
+            inspect::draw_pixel_grid(ctx, scene, main_view_size)?;
+        }
+
+        if let Some(comparison) = &self.comparison {
+            let mut text_compare = Text::new(comparison.label(scene), self.font.clone());
+            text_compare.draw(ctx, Vec2::new(view_size(ctx).x / 2.0 + 10.0, 10.0));
+        }
 
         /*
          * Draw Timer
          */
-        let mut text_steps = Text::new(format!("Time: {}", self.scene.timer), self.font.clone());
+        let mut text_steps = Text::new(format!("Time: {}", scene.timer), self.font.clone());
         text_steps.draw(ctx, Vec2::new(10.0, 10.0));
 
         /*
@@ -724,60 +1131,99 @@ impl State for GameState {
         /*
          * Draw Animation FPS
          */
-        let frame_rate = if let Timestep::Fixed(time_step) = time::get_timestep(ctx) {
-            time_step
-        } else {
-            1000f64
+        let rate = format!(
+            "APS: {} ({} fps x {})",
+            self.step_rate(),
+            scene.frame_rate,
+            self.playback_speed
+        );
+        let text = match self.tween_fps {
+            Some(fps) => format!("{} tweened to {} fps", rate, fps),
+            None => rate,
         };
-        let mut text_steps = Text::new(format!("APS: {}", frame_rate), self.font.clone());
+        let mut text_steps = Text::new(text, self.font.clone());
         text_steps.draw(ctx, Vec2::new(10.0, 50.0));
 
         /*
          * Draw Scale
          */
-        let mut text_scale = Text::new(format!("Scale: {}", self.scene.scale.x), self.font.clone());
+        let mut text_scale = Text::new(
+            format!(
+                "Scale: {:.2}{}",
+                scene.scale.x,
+                match self.filter_mode {
+                    FilterMode::Nearest => " (nearest)",
+                    _ => "",
+                }
+            ),
+            self.font.clone(),
+        );
         text_scale.draw(ctx, Vec2::new(10.0, 80.0));
 
         /*
-         * Draw Animation
+         * Draw Onion Skin
+         */
+        let mut text_onion = Text::new(
+            match self.onion_skin {
+                0 => String::from("Onion skin: off  [ ]"),
+                frames => format!("Onion skin: {} either side  [ ]", frames),
+            },
+            self.font.clone(),
+        );
+        text_onion.draw(ctx, Vec2::new(10.0, 100.0));
+
+        /*
+         * Draw Selected Sprite
          */
-        let mut text = String::from("Animations");
-        // loop over animations and list them put current one in brackets
-        for (index, animation) in self.scene.bsv3.animations.iter().enumerate() {
-            if index == self.scene.animation {
-                text = format!(
-                    "{}\n({:03} - {:03}) {}[{}] {}",
-                    text,
-                    animation.start,
-                    animation.end,
-                    if self.scene.always_draw_animations.contains(&index) {
-                        "[X]"
+        let text = match scene.selected_sprite {
+            Some(index) => {
+                let filter = &scene.sprite_filter;
+                format!(
+                    "Sprite: {}/{} {}{}{}{}",
+                    index,
+                    scene.bsv3.sprites.len(),
+                    scene.bsv3.sprites[index].name,
+                    if filter.is_visible(index) {
+                        ""
                     } else {
-                        "[ ]"
+                        " [hidden]"
                     },
-                    index,
-                    animation.name,
-                );
-            } else {
-                text = format!(
-                    "{}\n({:03} - {:03}) {} {}  {}",
-                    text,
-                    animation.start,
-                    animation.end,
-                    if self.scene.always_draw_animations.contains(&index) {
-                        "[X]"
+                    if filter.solo == Some(index) {
+                        " [solo]"
                     } else {
-                        "[ ]"
+                        ""
                     },
-                    index,
-                    animation.name,
-                );
+                    if filter.is_highlighted(index) {
+                        " [highlight]"
+                    } else {
+                        ""
+                    },
+                )
             }
+            None => String::from("Sprite: none (Tab or middle click)"),
+        };
+        let mut text_sprite = Text::new(text, self.font.clone());
+        text_sprite.draw(ctx, Vec2::new(10.0, 120.0));
+
+        /*
+         * Draw Pixel Readout
+         */
+        if self.inspect {
+            let point = scene.screen_to_asset(self.mouse_in_main_view(ctx), main_view_size);
+            let readout = inspect::PixelReadout::read(scene, point);
+            let mut text_readout = Text::new(readout.text(), self.font.clone());
+            let height = text_readout
+                .get_bounds(ctx)
+                .map_or(0.0, |bounds| bounds.height);
+            text_readout.draw(ctx, Vec2::new(10.0, view_size(ctx).y - 80.0 - height));
         }
 
-        let mut text_scale = Text::new(text, self.font.clone());
-        text_scale.draw(ctx, Vec2::new(10.0, 110.0));
+        /*
+         * Draw Animation List
+         */
+        self.animation_list
+            .draw(ctx, &self.font, scene, view_size(ctx));
 
-        Ok(())
+        self.draw_help(ctx)
     }
 }
@@ -0,0 +1,217 @@
+use colored::Colorize;
+use image::RgbaImage;
+use tsto_bsv3::ea::bsv3::BSV3;
+use tsto_bsv3::ea::render::{canvas_for_bounds, Renderer};
+use tsto_bsv3::ea::rgb::RGB;
+
+use crate::export;
+
+/// Largest channel difference [`verify`] accepts, from float rounding when sprites sample the
+/// texture at other coordinates.
+const TOLERANCE: u8 = 1;
+
+/// `bsv3 optimize <in.bsv3> <out.bsv3>`
+///
+/// Writes a copy of the asset with duplicate frames merged and unused entries dropped, see
+/// [`BSV3::optimize`], once every animation step renders the same as in the original.
+pub fn run(args: &[String]) {
+    let [in_path, out_path] = args else {
+        println!("{} bsv3 optimize <in.bsv3> <out.bsv3>", "Usage:".red());
+        return;
+    };
+
+    if let Err(error) = optimize(in_path, out_path) {
+        println!("{} {}", "Error:".red(), error);
+    }
+}
+
+fn optimize(in_path: &str, out_path: &str) -> Result<(), String> {
+    let (original, texture) = export::open_asset(in_path)?;
+    let size = |path: &str| std::fs::metadata(path).map_or(0, |metadata| metadata.len());
+    let before = size(in_path);
+
+    // Checked before anything is written, so a bad result never replaces a good file
+    let mut bsv3 = open(in_path)?;
+    let savings = bsv3.optimize();
+    let steps = verify(&original, &texture, &bsv3, &texture)?;
+    let bytes = bsv3
+        .to_bytes()
+        .map_err(|error| format!("could not write {}: {}", out_path, error))?;
+    write_files(&[(out_path, bytes)])?;
+
+    let written = open(out_path)?;
+    copy_texture(&original, &written)?;
+
+    let counts = [
+        ("Frames", savings.frames),
+        ("Groups", savings.groups),
+        ("Sprites", savings.sprites),
+    ];
+    for (name, (before, after)) in counts {
+        if before > 0 {
+            println!("{}: {} -> {}", name, before, after);
+        }
+    }
+    let after = size(out_path);
+    println!(
+        "Size: {} -> {} bytes ({:.1}% smaller)",
+        before,
+        after,
+        100.0 * before.saturating_sub(after) as f64 / before.max(1) as f64
+    );
+    println!(
+        "{} {} steps of {} animations render identically",
+        "Verified".green(),
+        steps,
+        written.animations.len()
+    );
+
+    Ok(())
+}
+
+fn open(path: &str) -> Result<BSV3, String> {
+    let mut bsv3 =
+        BSV3::open(path.into()).map_err(|error| format!("could not open {}: {}", path, error))?;
+    bsv3.parse()
+        .map_err(|error| format!("could not parse {}: {}", path, error))?;
+    Ok(bsv3)
+}
+
+/// Writes each file under a temporary name next to it, then renames them all into place, so a
+/// failed write leaves the existing files as they were.
+pub fn write_files(files: &[(&str, Vec<u8>)]) -> Result<(), String> {
+    let temporary = |path: &str| format!("{}.tmp", path);
+    for (index, (path, bytes)) in files.iter().enumerate() {
+        if let Err(error) = std::fs::write(temporary(path), bytes) {
+            for (written, _) in &files[..=index] {
+                let _ = std::fs::remove_file(temporary(written));
+            }
+            return Err(format!("could not write {}: {}", path, error));
+        }
+    }
+    for (path, _) in files {
+        std::fs::rename(temporary(path), path)
+            .map_err(|error| format!("could not write {}: {}", path, error))?;
+    }
+    Ok(())
+}
+
+/// Copies the atlas next to the output when it looks for one by a different name.
+fn copy_texture(original: &BSV3, written: &BSV3) -> Result<(), String> {
+    let (from, to) = (original.texture_path(), written.texture_path());
+    if from == to || std::path::Path::new(&to).exists() {
+        return Ok(());
+    }
+
+    std::fs::copy(&from, &to)
+        .map_err(|error| format!("could not copy {} to {}: {}", from, to, error))?;
+    println!("{} {}", "Copied texture to".blue(), to);
+    Ok(())
+}
+
+/// Renders every step of every animation from both files, each with its texture, failing on the
+/// first that differs by more than [`TOLERANCE`]. Returns the number of steps compared.
+pub fn verify(
+    original: &BSV3,
+    original_texture: &RGB,
+    written: &BSV3,
+    written_texture: &RGB,
+) -> Result<usize, String> {
+    if original.animations.len() != written.animations.len() {
+        return Err(format!(
+            "{} animations instead of {}",
+            written.animations.len(),
+            original.animations.len()
+        ));
+    }
+
+    let original_extents = original.sprite_extents(Some(original_texture));
+    let written_extents = written.sprite_extents(Some(written_texture));
+    let mut steps = 0;
+    for (animation, output) in original.animations.iter().zip(&written.animations) {
+        if output.frame_count() != animation.frame_count() {
+            return Err(format!(
+                "{} has {} steps instead of {}",
+                animation.name,
+                output.frame_count(),
+                animation.frame_count()
+            ));
+        }
+
+        let Some(bounds) = original
+            .animation_bounds(animation, &original_extents)
+            .into_iter()
+            .chain(written.animation_bounds(output, &written_extents))
+            .reduce(|bounds, other| bounds.union(&other))
+        else {
+            steps += animation.frame_count();
+            continue;
+        };
+        let (width, height, origin) = canvas_for_bounds(&bounds);
+        let before = Renderer::new(original, original_texture, origin);
+        let after = Renderer::new(written, written_texture, origin);
+
+        for (step, (frame_ref, output_ref)) in animation
+            .iter_frames()
+            .zip(output.iter_frames())
+            .enumerate()
+        {
+            let mut expected = RgbaImage::new(width, height);
+            before.draw(&mut expected, frame_ref);
+            let mut actual = RgbaImage::new(width, height);
+            after.draw(&mut actual, output_ref);
+
+            let differs = actual
+                .as_raw()
+                .iter()
+                .zip(expected.as_raw())
+                .any(|(&actual, &expected)| actual.abs_diff(expected) > TOLERANCE);
+            if differs {
+                return Err(format!(
+                    "step {} of {} renders differently in the output",
+                    step, animation.name
+                ));
+            }
+            steps += 1;
+        }
+    }
+
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Copy of the test asset in a folder of its own, for commands that write next to it.
+    fn scratch_copy(name: &str) -> String {
+        let folder = std::env::temp_dir().join(format!("bsv3_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        for file in ["building.bsv3", "building.rgb"] {
+            std::fs::copy(file, folder.join(file)).unwrap();
+        }
+        folder.join("building.bsv3").to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn optimizes_in_place() {
+        let path = scratch_copy("optimize");
+
+        optimize(&path, &path).unwrap();
+
+        assert!(open(&path).is_ok());
+        assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+    }
+
+    #[test]
+    fn failed_write_keeps_existing_files() {
+        let path = scratch_copy("write_files");
+        let before = std::fs::read(&path).unwrap();
+        let missing = format!("{}/missing/building.rgb", path);
+
+        assert!(write_files(&[(&path, vec![1, 2, 3]), (&missing, vec![])]).is_err());
+
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+        assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+    }
+}
@@ -0,0 +1,73 @@
+use tetra::graphics::text::{Font, Text};
+use tetra::graphics::{Color, DrawParams};
+use tetra::math::Vec2;
+use tetra::Context;
+
+/// Distance from the top of the view, below the recording indicator.
+const MENU_TOP: f32 = 40.0;
+const MENU_WIDTH: f32 = 520.0;
+const ROW_HEIGHT: f32 = 20.0;
+
+const CURRENT_COLOR: Color = Color::rgb(1.0, 0.85, 0.2);
+
+/// Recently opened files listed at the top right, opened by clicking them.
+#[derive(Default)]
+pub struct RecentMenu {
+    pub open: bool,
+    /// Row of the file being viewed, which `W` moves on from.
+    pub cursor: usize,
+}
+
+impl RecentMenu {
+    fn left(view_size: Vec2<f32>) -> f32 {
+        view_size.x - MENU_WIDTH - 10.0
+    }
+
+    /// Index into `files` of the row at `position`, if the menu is open.
+    pub fn file_at(
+        &self,
+        position: Vec2<f32>,
+        files: &[String],
+        view_size: Vec2<f32>,
+    ) -> Option<usize> {
+        if !self.open || position.x < Self::left(view_size) || position.y < MENU_TOP {
+            return None;
+        }
+
+        // Row 0 is the heading
+        let row = ((position.y - MENU_TOP) / ROW_HEIGHT) as usize;
+        row.checked_sub(1).filter(|&row| row < files.len())
+    }
+
+    pub fn draw(&self, ctx: &mut Context, font: &Font, files: &[String], view_size: Vec2<f32>) {
+        if !self.open {
+            return;
+        }
+
+        let left = Self::left(view_size);
+        let heading = if files.is_empty() {
+            "Recent files: none yet"
+        } else {
+            "Recent files (click to open, W for the next)"
+        };
+        Text::new(heading, font.clone()).draw(ctx, Vec2::new(left, MENU_TOP));
+
+        for (row, file) in files.iter().enumerate() {
+            // Keep the end of long paths, where the file name is
+            let shown = match file.char_indices().rev().nth(50) {
+                Some((index, _)) => format!("...{}", &file[index..]),
+                None => file.clone(),
+            };
+            Text::new(format!("{} {}", row + 1, shown), font.clone()).draw(
+                ctx,
+                DrawParams::default()
+                    .position(Vec2::new(left, MENU_TOP + ROW_HEIGHT * (row + 1) as f32))
+                    .color(if row == self.cursor {
+                        CURRENT_COLOR
+                    } else {
+                        Color::WHITE
+                    }),
+            );
+        }
+    }
+}
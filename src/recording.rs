@@ -0,0 +1,87 @@
+use std::thread::JoinHandle;
+
+use tsto_bsv3::ea::num::checked_lcm;
+use tsto_bsv3::ea::tween::tweened_frame_count;
+
+use crate::export::{self, ExportOptions, MAX_LOOP_FRAMES};
+use crate::scene::Scene;
+
+/// One loop of the viewer's composition being written to PNGs in the background.
+pub struct Recording {
+    pub out_dir: String,
+    /// Frames in the loop, as the viewer will report them.
+    pub frame_count: usize,
+    handle: JoinHandle<Result<usize, String>>,
+}
+
+impl Recording {
+    /// Records the current animation and the frozen ones at 1:1, with the scene's sprite filter,
+    /// from the files on disk so the viewer keeps running. Tweens from the scene's frame rate to
+    /// `tween_fps` if given.
+    pub fn start(scene: &Scene, out_dir: &str, tween_fps: Option<f64>) -> Recording {
+        let mut animations = vec![scene.animation];
+        for &animation in &scene.always_draw_animations {
+            if !animations.contains(&animation) {
+                animations.push(animation);
+            }
+        }
+
+        let lengths = animations
+            .iter()
+            .map(|&id| scene.bsv3.animations[id].frame_count())
+            .collect::<Vec<usize>>();
+        let frame_count = checked_lcm(&lengths).map_or(MAX_LOOP_FRAMES, |steps| {
+            let frames = match tween_fps {
+                Some(fps) => tweened_frame_count(steps, scene.frame_rate, fps),
+                None => steps,
+            };
+            frames.min(MAX_LOOP_FRAMES)
+        });
+
+        // Animations and the sprite filter are matched by name in case the file changed since it
+        // was loaded
+        let names = animations
+            .iter()
+            .map(|&id| scene.bsv3.animations[id].name.clone())
+            .collect::<Vec<String>>();
+        let path = scene.path.clone();
+        let sprites = scene.bsv3.sprites.clone();
+        let mut options = ExportOptions {
+            sprite_filter: scene.sprite_filter.clone(),
+            frame_rate: scene.frame_rate,
+            tween_fps,
+            ..ExportOptions::default()
+        };
+        let thread_out_dir = out_dir.to_string();
+
+        let handle = std::thread::spawn(move || {
+            let (bsv3, texture) = export::open_asset(&path)?;
+            let animations = names
+                .iter()
+                .filter_map(|name| bsv3.animations.iter().position(|anim| &anim.name == name))
+                .collect::<Vec<usize>>();
+            options.sprite_filter = options.sprite_filter.remap(&sprites, &bsv3.sprites);
+
+            std::fs::create_dir_all(&thread_out_dir)
+                .map_err(|error| format!("could not create {}: {}", thread_out_dir, error))?;
+            export::export_loop(&bsv3, &texture, &animations, &thread_out_dir, &options)
+        });
+
+        Recording {
+            out_dir: out_dir.to_string(),
+            frame_count,
+            handle,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the recording, returning the number of frames written.
+    pub fn finish(self) -> Result<usize, String> {
+        self.handle
+            .join()
+            .unwrap_or_else(|_| Err(String::from("recording failed")))
+    }
+}
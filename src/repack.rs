@@ -0,0 +1,145 @@
+use colored::Colorize;
+
+use crate::{export, optimize};
+
+/// `bsv3 repack <in.bsv3> <out.bsv3> [--padding N] [--pot]`
+///
+/// Writes a copy of the asset whose texture holds only the sprite regions it uses, packed tightly,
+/// see [`tsto_bsv3::ea::bsv3::BSV3::repack`], once every animation step renders the same as in the
+/// original.
+pub fn run(args: &[String]) {
+    let mut paths = vec![];
+    let mut padding = 0;
+    let mut power_of_two = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--padding" => match args.next().map(|value| (value, value.parse())) {
+                Some((_, Ok(value))) => padding = value,
+                Some((value, Err(_))) => {
+                    println!("{} invalid padding {}", "Warning:".yellow(), value)
+                }
+                None => {}
+            },
+            "--pot" => power_of_two = true,
+            _ => paths.push(arg.clone()),
+        }
+    }
+
+    let [in_path, out_path] = paths.as_slice() else {
+        println!(
+            "{} bsv3 repack <in.bsv3> <out.bsv3> [--padding N] [--pot]",
+            "Usage:".red()
+        );
+        return;
+    };
+
+    if let Err(error) = repack(in_path, out_path, padding, power_of_two) {
+        println!("{} {}", "Error:".red(), error);
+    }
+}
+
+fn repack(in_path: &str, out_path: &str, padding: u32, power_of_two: bool) -> Result<(), String> {
+    let (original, original_texture) = export::open_asset(in_path)?;
+
+    // Named after the output, which is where BSV3 files without an image name look for it
+    let path = std::path::Path::new(out_path);
+    let name = path
+        .file_name()
+        .map_or(String::new(), |name| name.to_string_lossy().to_string());
+    let texture_name = format!("{}.rgb", name.split('.').next().unwrap_or_default());
+    let texture_path = path
+        .with_file_name(&texture_name)
+        .to_string_lossy()
+        .to_string();
+    if same_file(&texture_path, &original.texture_path()) && !same_file(in_path, out_path) {
+        return Err(format!(
+            "writing {} would replace the texture {} still uses",
+            texture_path, in_path
+        ));
+    }
+
+    let (mut bsv3, mut texture) = export::open_asset(in_path)?;
+    if !bsv3.image_name.is_empty() {
+        bsv3.image_name = texture_name;
+    }
+    let repacked = bsv3
+        .repack(&mut texture, padding, power_of_two)
+        .map_err(|error| format!("could not repack {}: {}", in_path, error))?;
+
+    // Checked before anything is written, so an in-place repack never loses the original
+    let steps = optimize::verify(&original, &original_texture, &bsv3, &texture)?;
+    let bytes = bsv3
+        .to_bytes()
+        .map_err(|error| format!("could not write {}: {}", out_path, error))?;
+    let texture_bytes = texture
+        .to_bytes()
+        .map_err(|error| format!("could not write {}: {}", texture_path, error))?;
+    optimize::write_files(&[(out_path, bytes), (&texture_path, texture_bytes)])?;
+
+    println!(
+        "Atlas: {}x{} -> {}x{}",
+        repacked.before.0, repacked.before.1, repacked.after.0, repacked.after.1
+    );
+    println!(
+        "Utilisation: {:.1}% -> {:.1}%",
+        repacked.utilisation_before() * 100.0,
+        repacked.utilisation_after() * 100.0
+    );
+    println!("{} {}", "Wrote".green(), texture_path);
+    println!(
+        "{} {} steps of {} animations render identically",
+        "Verified".green(),
+        steps,
+        bsv3.animations.len()
+    );
+
+    Ok(())
+}
+
+/// Whether `a` and `b` name the same file, which need not exist yet as long as its folder does.
+fn same_file(a: &str, b: &str) -> bool {
+    let canonical = |path: &str| {
+        let path = std::path::Path::new(path);
+        std::fs::canonicalize(path).ok().or_else(|| {
+            let folder = match path.parent() {
+                Some(folder) if !folder.as_os_str().is_empty() => folder,
+                _ => std::path::Path::new("."),
+            };
+            Some(std::fs::canonicalize(folder).ok()?.join(path.file_name()?))
+        })
+    };
+    match (canonical(a), canonical(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_file_ignores_how_the_path_is_written() {
+        assert!(same_file("./Cargo.toml", "Cargo.toml"));
+        assert!(same_file("src/../building.rgb", "building.rgb"));
+        assert!(same_file("./not_written_yet.rgb", "not_written_yet.rgb"));
+        assert!(!same_file("building.rgb", "building.bsv3"));
+    }
+
+    #[test]
+    fn repacks_in_place() {
+        let folder = std::env::temp_dir().join(format!("bsv3_repack_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        for file in ["building.bsv3", "building.rgb"] {
+            std::fs::copy(file, folder.join(file)).unwrap();
+        }
+        let path = folder.join("building.bsv3").to_string_lossy().to_string();
+
+        repack(&path, &path, 1, true).unwrap();
+
+        let (_, texture) = export::open_asset(&path).unwrap();
+        assert!(texture.width.is_power_of_two() && texture.height.is_power_of_two());
+    }
+}
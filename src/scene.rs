@@ -0,0 +1,556 @@
+use colored::Colorize;
+use tetra::graphics::mesh::{
+    BufferUsage, GeometryBuilder, IndexBuffer, Mesh, Vertex, VertexBuffer,
+};
+use tetra::graphics::{
+    self, BlendState, Canvas, Color, DrawParams, FilterMode, Texture, TextureFormat,
+};
+use tetra::math::{Mat4, Vec2};
+use tetra::{Context, TetraError};
+use tsto_bsv3::ea::bsv3::{FrameRef, BSV3};
+use tsto_bsv3::ea::building::Building;
+use tsto_bsv3::ea::draw_list::DrawCommand;
+use tsto_bsv3::ea::frame_rate;
+use tsto_bsv3::ea::geometry::{Affine2, Rect};
+use tsto_bsv3::ea::overlay::Overlays;
+use tsto_bsv3::ea::rgb::RGB;
+use tsto_bsv3::ea::sprite_filter::SpriteFilter;
+
+use crate::watcher::FileWatcher;
+use crate::{CANVAS_HALF, CANVAS_SIZE};
+
+/// Share of the view an asset fills after [`Scene::fit`].
+const FIT_MARGIN: f32 = 0.9;
+
+pub const MIN_SCALE: f32 = 1.0 / 16.0;
+/// High enough to inspect single pixels.
+pub const MAX_SCALE: f32 = 32.0;
+
+/// Opacity of the nearest onion skin frame, further ones fade out linearly.
+const ONION_OPACITY: f32 = 0.5;
+/// Tints for frames before and after the current one.
+const ONION_PREVIOUS: Color = Color::rgb(1.0, 0.3, 0.3);
+const ONION_NEXT: Color = Color::rgb(0.3, 1.0, 0.3);
+
+/// Two triangles covering a sprite quad.
+const QUAD_INDICES: [u32; 6] = [0, 1, 2, 0, 2, 3];
+
+pub struct Scene {
+    pub path: String,
+    pub watcher: FileWatcher,
+    pub bsv3: BSV3,
+    pub texture: Texture,
+    /// CPU copy of the atlas for the software renderer and pixel inspection.
+    pub rgb: RGB,
+    /// Current animation step, counting up from 0.
+    pub timer: usize,
+    /// How far playback is towards the step after `timer`.
+    pub step_fraction: f32,
+    /// Animation steps per second at normal playback speed.
+    pub frame_rate: f64,
+    /// `frame_rate` from the override file, to tell whether the viewer changed it.
+    file_frame_rate: f64,
+    pub scale: Vec2<f32>,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub animation: usize,
+    pub always_draw_animations: Vec<usize>,
+    /// Quads of the current and frozen animations drawn by the last call to [`Scene::draw`],
+    /// back to front, before the sprite filter.
+    pub drawn: Vec<DrawCommand>,
+    /// Step of the current animation the last call to [`Scene::draw`] showed.
+    pub drawn_step: usize,
+    /// Sprites hidden, soloed or highlighted in every frame.
+    pub sprite_filter: SpriteFilter,
+    /// Sprite the filter keys act on.
+    pub selected_sprite: Option<usize>,
+    /// Footprint and anchor from the building XML, if there is one.
+    pub building: Option<Building>,
+    /// Alpha tight bounds of every animation, in asset space.
+    pub bounds: Option<Rect>,
+    /// One quad per `bsv3.sprites` entry, positioned at draw time by the layer transform.
+    sprite_meshes: Vec<Mesh>,
+}
+
+impl Scene {
+    pub fn new(ctx: &mut Context, file_path: &str) -> Result<Scene, TetraError> {
+        let mut bsv3 = BSV3::open(String::from(file_path)).map_err(|reason| {
+            TetraError::FailedToLoadAsset {
+                reason,
+                path: file_path.into(),
+            }
+        })?;
+
+        bsv3.parse()
+            .map_err(|reason| TetraError::FailedToLoadAsset {
+                reason,
+                path: file_path.into(),
+            })?;
+
+        let texture_path = bsv3.texture_path();
+        let mut rgb =
+            RGB::open(texture_path.clone()).map_err(|reason| TetraError::FailedToLoadAsset {
+                reason,
+                path: texture_path.clone().into(),
+            })?;
+        rgb.parse()
+            .map_err(|reason| TetraError::FailedToLoadAsset {
+                reason,
+                path: texture_path.clone().into(),
+            })?;
+
+        let mut texture = Texture::from_data(
+            ctx,
+            rgb.width as i32,
+            rgb.height as i32,
+            TextureFormat::Rgba8,
+            &rgb.data,
+        )?;
+        texture.set_filter_mode(ctx, FilterMode::Linear);
+
+        let sprite_meshes = Self::build_sprite_meshes(ctx, &bsv3, &texture)?;
+        let bounds = bsv3.bounds(&bsv3.sprite_extents(Some(&rgb)));
+
+        let building_path = bsv3.building_xml_path();
+        let building = match Building::open(&building_path) {
+            Ok(building) => Some(building),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+            Err(error) => {
+                println!("{} {}: {}", "Warning:".yellow(), building_path, error);
+                None
+            }
+        };
+
+        let frame_rate = frame_rate::frame_rate_for(file_path);
+
+        let watcher = FileWatcher::new(vec![
+            file_path.into(),
+            texture_path.into(),
+            building_path.into(),
+            frame_rate::override_path(file_path).into(),
+        ]);
+
+        Ok(Scene {
+            path: String::from(file_path),
+            watcher,
+            bsv3,
+            texture,
+            rgb,
+            timer: 0,
+            step_fraction: 0.0,
+            frame_rate,
+            file_frame_rate: frame_rate,
+            scale: Vec2::new(1.0, 1.0),
+            offset_x: 0.0,
+            offset_y: 400.0,
+            animation: 0,
+            always_draw_animations: vec![],
+            drawn: vec![],
+            drawn_step: 0,
+            sprite_filter: SpriteFilter::default(),
+            selected_sprite: None,
+            building,
+            bounds,
+            sprite_meshes,
+        })
+    }
+
+    /// Centres the asset and zooms so its bounds fill a `width` by `height` view.
+    pub fn fit(&mut self, width: f32, height: f32) {
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+
+        let (center_x, center_y) = bounds.center();
+        self.offset_x = -center_x;
+        self.offset_y = -center_y;
+
+        let scale = (width / bounds.width).min(height / bounds.height) * FIT_MARGIN;
+        self.scale = Vec2::broadcast(scale.clamp(MIN_SCALE, MAX_SCALE));
+    }
+
+    /// Multiplies the zoom by `factor`, keeping the point under `anchor` in place.
+    ///
+    /// `anchor` is relative to the centre of the view, where the canvas centre is drawn.
+    pub fn zoom_at(&mut self, factor: f32, anchor: Vec2<f32>) {
+        let scale = (self.scale.x * factor).clamp(MIN_SCALE, MAX_SCALE);
+
+        // screen = centre + (point + offset) * scale, solved for the new offset
+        self.offset_x += anchor.x / scale - anchor.x / self.scale.x;
+        self.offset_y += anchor.y / scale - anchor.y / self.scale.y;
+        self.scale = Vec2::broadcast(scale);
+    }
+
+    /// Position in asset space under `screen`.
+    pub fn screen_to_asset(&self, screen: Vec2<f32>, view_size: Vec2<f32>) -> (f32, f32) {
+        let point = (screen - view_size / 2.0) / self.scale;
+        (point.x - self.offset_x, point.y - self.offset_y)
+    }
+
+    /// Position on screen of `point` in asset space.
+    pub fn asset_to_screen(&self, (x, y): (f32, f32), view_size: Vec2<f32>) -> Vec2<f32> {
+        view_size / 2.0 + (Vec2::new(x + self.offset_x, y + self.offset_y)) * self.scale
+    }
+
+    pub fn set_filter_mode(&mut self, ctx: &mut Context, filter_mode: FilterMode) {
+        self.texture.set_filter_mode(ctx, filter_mode);
+    }
+
+    /// Top left of the canvas on screen so the canvas centre lands in the middle of the view.
+    pub fn canvas_position(&self, view_size: Vec2<f32>) -> Vec2<f32> {
+        (view_size - Vec2::broadcast(CANVAS_SIZE) * self.scale) / 2.0
+    }
+
+    /// Carries zoom, pan, timer, animation, freezes and a frame rate picked in the viewer over from
+    /// the scene this one replaces, matching animations by name since a re-export may reorder or drop them.
+    pub fn keep_view(&mut self, old: &Scene) {
+        self.timer = old.timer;
+        self.step_fraction = old.step_fraction;
+        self.scale = old.scale;
+        self.offset_x = old.offset_x;
+        self.offset_y = old.offset_y;
+        // A rate picked in the viewer is kept, otherwise the override file may have changed
+        if old.frame_rate != old.file_frame_rate {
+            self.frame_rate = old.frame_rate;
+        }
+
+        self.sprite_filter = old
+            .sprite_filter
+            .remap(&old.bsv3.sprites, &self.bsv3.sprites);
+        self.selected_sprite = old.selected_sprite.and_then(|index| {
+            let name = &old.bsv3.sprites.get(index)?.name;
+            self.bsv3
+                .sprites
+                .iter()
+                .position(|sprite| &sprite.name == name)
+        });
+
+        let find = |index: usize| self.find_animation(&old.bsv3.animations.get(index)?.name);
+        let animation = find(old.animation).unwrap_or(0);
+        let always_draw_animations = old
+            .always_draw_animations
+            .iter()
+            .filter_map(|&index| find(index))
+            .collect();
+
+        self.animation = animation;
+        self.always_draw_animations = always_draw_animations;
+    }
+
+    /// Index of the animation called `name`.
+    pub fn find_animation(&self, name: &str) -> Option<usize> {
+        self.bsv3
+            .animations
+            .iter()
+            .position(|animation| animation.name == name)
+    }
+
+    fn build_sprite_meshes(
+        ctx: &mut Context,
+        bsv3: &BSV3,
+        texture: &Texture,
+    ) -> Result<Vec<Mesh>, TetraError> {
+        let texture_width = texture.width() as f32;
+        let texture_height = texture.height() as f32;
+        let index_buffer = IndexBuffer::new(ctx, &QUAD_INDICES)?;
+
+        bsv3.sprites
+            .iter()
+            .map(|sprite| {
+                let width = sprite.width as f32;
+                let height = sprite.height as f32;
+                let left = sprite.x as f32 / texture_width;
+                let top = sprite.y as f32 / texture_height;
+                let right = (sprite.x + sprite.width) as f32 / texture_width;
+                let bottom = (sprite.y + sprite.height) as f32 / texture_height;
+
+                let vertices = [
+                    Vertex::new(Vec2::new(0.0, 0.0), Vec2::new(left, top), Color::WHITE),
+                    Vertex::new(Vec2::new(width, 0.0), Vec2::new(right, top), Color::WHITE),
+                    Vertex::new(
+                        Vec2::new(width, height),
+                        Vec2::new(right, bottom),
+                        Color::WHITE,
+                    ),
+                    Vertex::new(
+                        Vec2::new(0.0, height),
+                        Vec2::new(left, bottom),
+                        Color::WHITE,
+                    ),
+                ];
+
+                let mut mesh =
+                    VertexBuffer::with_usage(ctx, &vertices, BufferUsage::Static)?.into_mesh();
+                mesh.set_backface_culling(false);
+                mesh.set_index_buffer(index_buffer.clone());
+                mesh.set_texture(texture.clone());
+
+                Ok(mesh)
+            })
+            .collect()
+    }
+
+    fn command_transform(&self, command: &DrawCommand) -> Mat4<f32> {
+        let transform = command.transform.then(&Affine2::translation(
+            CANVAS_HALF + self.offset_x,
+            CANVAS_HALF + self.offset_y,
+        ));
+
+        affine_to_mat4(&transform)
+    }
+
+    /// Moves playback on by `steps`, which may be a fraction of a step.
+    pub fn advance(&mut self, steps: f32) {
+        self.step_fraction += steps;
+        let whole = self.step_fraction.floor();
+        self.timer += whole as usize;
+        self.step_fraction -= whole;
+    }
+
+    /// Freezes or unfreezes `animation`, which is then drawn along with the current one.
+    pub fn toggle_frozen(&mut self, animation: usize) {
+        if self.always_draw_animations.contains(&animation) {
+            self.always_draw_animations.retain(|&x| x != animation);
+        } else {
+            self.always_draw_animations.push(animation);
+        }
+    }
+
+    pub fn get_index_for_animation(&self, animation_id: usize) -> FrameRef {
+        self.bsv3.animations[animation_id].frame_at(self.timer)
+    }
+
+    /// Quads drawn by the last tick that pass the sprite filter, back to front.
+    pub fn visible_commands(&self) -> Vec<DrawCommand> {
+        self.drawn
+            .iter()
+            .filter(|command| self.sprite_filter.is_visible(command.sprite))
+            .cloned()
+            .collect()
+    }
+
+    /// Topmost visible sprite at `point` in asset space.
+    pub fn sprite_at(&self, point: (f32, f32)) -> Option<usize> {
+        self.visible_commands()
+            .into_iter()
+            .rfind(|command| command.hit_test(point))
+            .map(|command| command.sprite)
+    }
+
+    /// Draws quads multiplied by `tint`, whose alpha scales every layer's.
+    fn draw_commands(&self, ctx: &mut Context, commands: &[DrawCommand], tint: Color) {
+        for command in commands {
+            if !self.sprite_filter.is_visible(command.sprite) {
+                continue;
+            }
+            let Some(mesh) = self.sprite_meshes.get(command.sprite) else {
+                continue;
+            };
+
+            let [r, g, b] = self.sprite_filter.tint(command.sprite);
+            let tint = Color::rgba(tint.r * r, tint.g * g, tint.b * b, tint.a);
+
+            // Premultiplied, like the texture
+            let alpha = command.alpha * tint.a;
+
+            graphics::set_transform_matrix(ctx, self.command_transform(command));
+            mesh.draw(
+                ctx,
+                DrawParams::default().color(Color::rgba(
+                    tint.r * alpha,
+                    tint.g * alpha,
+                    tint.b * alpha,
+                    alpha,
+                )),
+            );
+        }
+    }
+
+    /// Up to `count` steps either side of the current one, furthest first so nearer ones draw on
+    /// top, each paired with its tint.
+    fn onion_skin_frames(&self, count: usize) -> Vec<(FrameRef, Color)> {
+        let Some(animation) = self.bsv3.animations.get(self.animation) else {
+            return vec![];
+        };
+        let frame_count = animation.frame_count();
+        // Wrapping further would show the current frame or repeat others, so the other steps are
+        // shared out with any odd one going before, e.g. just the previous step of two
+        let before = count.min(frame_count / 2);
+        let after = count.min((frame_count - 1) / 2);
+
+        let mut frames = vec![];
+        for distance in (1..=before).rev() {
+            let opacity = ONION_OPACITY * (before + 1 - distance) as f32 / before as f32;
+            frames.push((
+                animation.frame_at(self.timer + frame_count - distance),
+                ONION_PREVIOUS.with_alpha(opacity),
+            ));
+            if distance <= after {
+                frames.push((
+                    animation.frame_at(self.timer + distance),
+                    ONION_NEXT.with_alpha(opacity),
+                ));
+            }
+        }
+        frames
+    }
+
+    /// Renders the current tick into `canvas` and advances the timer by `steps`.
+    ///
+    /// With `onion_skin` above zero that many steps before and after the current animation step
+    /// are drawn behind it, tinted red and green. With `tween`, a timer between two steps
+    /// interpolates them, otherwise the step it is on is shown as is.
+    pub fn draw(
+        &mut self,
+        ctx: &mut Context,
+        canvas: &Canvas,
+        onion_skin: usize,
+        steps: f32,
+        tween: bool,
+    ) {
+        graphics::set_canvas(ctx, canvas);
+        graphics::clear(ctx, Color::rgba(0.0, 0.0, 0.0, 0.0));
+        self.draw_tick(ctx, onion_skin, steps, tween);
+        graphics::reset_canvas(ctx);
+    }
+
+    /// Like [`Scene::draw`] but over whatever the current canvas holds, for several scenes on
+    /// one canvas.
+    pub fn draw_tick(&mut self, ctx: &mut Context, onion_skin: usize, steps: f32, tween: bool) {
+        graphics::set_blend_state(ctx, BlendState::alpha(true));
+
+        if !self.bsv3.animations.is_empty() {
+            for (frame_ref, tint) in self.onion_skin_frames(onion_skin) {
+                self.draw_commands(ctx, &self.bsv3.draw_list(frame_ref), tint);
+            }
+
+            // Frozen animations showing the same frame as the one before are drawn once
+            let mut animations = vec![self.animation];
+            animations.extend(&self.always_draw_animations);
+            animations.dedup_by_key(|&mut animation| self.get_index_for_animation(animation));
+
+            let drawn = animations
+                .iter()
+                .flat_map(|&animation| {
+                    step_draw_list(&self.bsv3, animation, self.timer, self.step_fraction, tween)
+                })
+                .collect::<Vec<DrawCommand>>();
+            self.draw_commands(ctx, &drawn, Color::WHITE);
+
+            self.drawn = drawn;
+            self.drawn_step = self.timer;
+        }
+
+        self.advance(steps);
+
+        graphics::reset_transform_matrix(ctx);
+        graphics::reset_blend_state(ctx);
+    }
+
+    /// Draws `overlays` for the frames of the last tick in screen space, so lines stay one pixel
+    /// wide at any zoom.
+    pub fn draw_overlays(
+        &self,
+        ctx: &mut Context,
+        overlays: &Overlays,
+        view_size: Vec2<f32>,
+    ) -> tetra::Result {
+        if !overlays.any() {
+            return Ok(());
+        }
+
+        let commands = self.visible_commands();
+        let (left, top) = self.screen_to_asset(Vec2::zero(), view_size);
+        let (right, bottom) = self.screen_to_asset(view_size, view_size);
+        let area = Rect::new(left, top, right - left, bottom - top);
+
+        let lines = overlays.lines(&commands, &area);
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder = GeometryBuilder::new();
+        for line in lines {
+            let [r, g, b, a] = line.color;
+            builder.set_color(Color::rgba8(r, g, b, a));
+            builder.polyline(
+                1.0,
+                &[
+                    self.asset_to_screen(line.from, view_size),
+                    self.asset_to_screen(line.to, view_size),
+                ],
+            )?;
+        }
+        builder.build_mesh(ctx)?.draw(ctx, DrawParams::default());
+
+        Ok(())
+    }
+}
+
+/// Quads for animation `animation` at `step`, `fraction` of the way to the next step when
+/// tweening and a hard cut otherwise.
+fn step_draw_list(
+    bsv3: &BSV3,
+    animation: usize,
+    step: usize,
+    fraction: f32,
+    tween: bool,
+) -> Vec<DrawCommand> {
+    let fraction = if tween { fraction } else { 0.0 };
+    bsv3.tweened_draw_list(&bsv3.animations[animation], step, fraction)
+}
+
+pub fn affine_to_mat4(transform: &Affine2) -> Mat4<f32> {
+    Mat4::new(
+        transform.a,
+        transform.b,
+        0.0,
+        transform.tx,
+        transform.c,
+        transform.d,
+        0.0,
+        transform.ty,
+        0.0,
+        0.0,
+        1.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        1.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn untweened_steps_cut_between_frames() {
+        let mut bsv3 = BSV3::new(String::from("building.bsv3"));
+        bsv3.parse().unwrap();
+        // A second step with every layer moved, so tweening has something to interpolate
+        let mut moved = bsv3.frames[0].clone();
+        for layer in &mut moved.layers {
+            layer.x += 10.0;
+        }
+        bsv3.frames.push(moved);
+        bsv3.animations[0].start = 0;
+        bsv3.animations[0].end = (bsv3.frames.len() - 1) as u16;
+        let step = bsv3.frames.len() - 2;
+        let animation = &bsv3.animations[0];
+
+        assert_eq!(
+            step_draw_list(&bsv3, 0, step, 0.5, false),
+            bsv3.draw_list(animation.frame_at(step))
+        );
+        assert_eq!(
+            step_draw_list(&bsv3, 0, step, 0.5, true),
+            bsv3.tweened_draw_list(animation, step, 0.5)
+        );
+        assert_ne!(
+            step_draw_list(&bsv3, 0, step, 0.5, true),
+            bsv3.draw_list(animation.frame_at(step))
+        );
+    }
+}
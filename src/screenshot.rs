@@ -0,0 +1,75 @@
+use image::{GenericImageView, RgbaImage};
+use tsto_bsv3::ea::render::{canvas_for_bounds, unpremultiply, Renderer};
+
+use crate::background::{flatten, Background};
+use crate::scene::Scene;
+
+/// Saves what the scene drew last at 1:1 to `out_dir`, cropped to the visible pixels, over
+/// `background` or transparent. Returns the path written.
+pub fn save(
+    scene: &Scene,
+    out_dir: &str,
+    background: Option<&Background>,
+) -> Result<String, String> {
+    let bounds = scene
+        .visible_commands()
+        .iter()
+        .filter(|command| command.alpha > 0.0)
+        .map(|command| {
+            command
+                .transform
+                .transform_rect_bounds(&command.local_rect())
+        })
+        .reduce(|bounds, quad| bounds.union(&quad))
+        .ok_or("nothing is drawn")?;
+
+    let (width, height, origin) = canvas_for_bounds(&bounds);
+    let renderer = Renderer::new(&scene.bsv3, &scene.rgb, origin).with_filter(&scene.sprite_filter);
+    let mut canvas = RgbaImage::new(width, height);
+    renderer.draw_commands(&mut canvas, &scene.drawn);
+
+    let mut canvas = crop_transparent(&canvas).ok_or("nothing is drawn")?;
+    match background {
+        Some(background) => {
+            let below = background
+                .render(canvas.width(), canvas.height())
+                .map_err(|error| format!("could not load {}: {}", background, error))?;
+            flatten(&mut canvas, &below);
+        }
+        None => unpremultiply(&mut canvas),
+    }
+
+    let animation = &scene.bsv3.animations[scene.animation];
+    let step = scene.drawn_step % animation.frame_count();
+    let path = format!(
+        "{}/{}_{}_{:03}.png",
+        out_dir, scene.bsv3.file.name, animation.name, step
+    );
+
+    std::fs::create_dir_all(out_dir)
+        .and_then(|_| canvas.save(&path).map_err(std::io::Error::other))
+        .map_err(|error| format!("could not save {}: {}", path, error))?;
+
+    Ok(path)
+}
+
+/// The smallest part of `image` holding every pixel that isn't fully transparent.
+fn crop_transparent(image: &RgbaImage) -> Option<RgbaImage> {
+    let opaque = image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel[3] > 0)
+        .map(|(x, y, _)| (x, y));
+
+    let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y) in opaque {
+        left = left.min(x);
+        top = top.min(y);
+        right = right.max(x + 1);
+        bottom = bottom.max(y + 1);
+    }
+    if left >= right {
+        return None;
+    }
+
+    Some(image.view(left, top, right - left, bottom - top).to_image())
+}
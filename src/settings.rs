@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+
+use colored::Colorize;
+use tsto_bsv3::ea::overlay::Overlays;
+
+use crate::background::Background;
+use crate::{MAX_PLAYBACK_SPEED, MIN_PLAYBACK_SPEED};
+
+/// Most files kept in [`Settings::recent_files`].
+pub const MAX_RECENT_FILES: usize = 10;
+
+/// Viewer choices remembered between runs, stored as `key = value` lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub background: Option<Background>,
+    /// Nearest-neighbour instead of linear filtering.
+    pub nearest_filter: bool,
+    /// Multiplies the animation frame rate.
+    pub playback_speed: f64,
+    pub overlays: Overlays,
+    pub window_size: Option<(i32, i32)>,
+    /// Most recently opened first.
+    pub recent_files: Vec<String>,
+    /// `bind <action> = <keys>` lines, applied over the default key bindings.
+    pub key_bindings: Vec<(String, String)>,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            background: None,
+            nearest_filter: false,
+            playback_speed: 1.0,
+            overlays: Overlays::default(),
+            window_size: None,
+            recent_files: vec![],
+            key_bindings: vec![],
+        }
+    }
+}
+
+impl Settings {
+    /// `tsto-bsv3/settings.txt` in the user's config directory.
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("tsto-bsv3").join("settings.txt"))
+    }
+
+    /// Missing or unreadable settings fall back to the defaults, one value at a time.
+    pub fn load() -> Settings {
+        Self::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map_or_else(Settings::default, |contents| Settings::parse(&contents))
+    }
+
+    pub fn parse(contents: &str) -> Settings {
+        let mut settings = Settings::default();
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "background" => settings.background = Background::parse(value),
+                "filter" => settings.nearest_filter = value == "nearest",
+                "speed" => {
+                    // A speed of zero or less would stop the clock, so those are ignored
+                    if let Some(speed) = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|speed| speed.is_finite() && *speed > 0.0)
+                    {
+                        settings.playback_speed =
+                            speed.clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+                    }
+                }
+                "overlays" => settings.overlays = Overlays::parse(value).unwrap_or_default(),
+                "window" => {
+                    settings.window_size = value.split_once('x').and_then(|(width, height)| {
+                        Some((width.parse().ok()?, height.parse().ok()?))
+                    })
+                }
+                "recent" if settings.recent_files.len() < MAX_RECENT_FILES => {
+                    settings.recent_files.push(value.to_string())
+                }
+                key if key.starts_with("bind ") => settings
+                    .key_bindings
+                    .push((key["bind ".len()..].trim().to_string(), value.to_string())),
+                _ => {}
+            }
+        }
+
+        settings
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+
+        let mut contents = String::new();
+        if let Some(background) = &self.background {
+            contents += &format!("background = {}\n", background);
+        }
+        let filter = if self.nearest_filter {
+            "nearest"
+        } else {
+            "linear"
+        };
+        contents += &format!("filter = {}\n", filter);
+        contents += &format!("speed = {}\n", self.playback_speed);
+        contents += &format!("overlays = {}\n", self.overlays);
+        if let Some((width, height)) = self.window_size {
+            contents += &format!("window = {}x{}\n", width, height);
+        }
+        for file in &self.recent_files {
+            contents += &format!("recent = {}\n", file);
+        }
+        for (action, keys) in &self.key_bindings {
+            contents += &format!("bind {} = {}\n", action, keys);
+        }
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, contents));
+        if let Err(error) = result {
+            println!(
+                "{} could not save {}: {}",
+                "Warning:".yellow(),
+                path.display(),
+                error
+            );
+        }
+    }
+
+    /// Moves `file_path` to the front of the recent files.
+    pub fn add_recent_file(&mut self, file_path: &str) {
+        let file_path = std::fs::canonicalize(file_path).map_or(file_path.to_string(), |path| {
+            path.to_string_lossy().to_string()
+        });
+        self.recent_files.retain(|file| file != &file_path);
+        self.recent_files.insert(0, file_path);
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_is_positive_and_clamped() {
+        let speed = |line: &str| Settings::parse(line).playback_speed;
+        assert_eq!(speed("speed = 2"), 2.0);
+        assert_eq!(speed("speed = 100"), MAX_PLAYBACK_SPEED);
+        assert_eq!(speed("speed = 0.001"), MIN_PLAYBACK_SPEED);
+        for invalid in ["0", "-1", "inf", "NaN", "fast"] {
+            assert_eq!(speed(&format!("speed = {}", invalid)), 1.0);
+        }
+    }
+}
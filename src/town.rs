@@ -0,0 +1,356 @@
+use tetra::graphics::mesh::GeometryBuilder;
+use tetra::graphics::{self, Canvas, Color, DrawParams, FilterMode};
+use tetra::math::Vec2;
+use tetra::Context;
+use tsto_bsv3::ea::building::{self, Footprint};
+use tsto_bsv3::ea::geometry::Rect;
+use tsto_bsv3::ea::overlay::{Line, Overlays};
+use tsto_bsv3::ea::render::canvas_for_bounds;
+
+use crate::scene::{Scene, MAX_SCALE, MIN_SCALE};
+use crate::CANVAS_HALF;
+
+/// Share of the view the town fills after [`Town::fit`].
+const FIT_MARGIN: f32 = 0.9;
+
+/// Pixels kept around the buildings' bounds on the town canvas, for filtering at their edges.
+const CANVAS_MARGIN: f32 = 2.0;
+
+/// Largest side of the town canvas, within what GPUs allow for a texture. Towns spreading further
+/// are cut off at the right and bottom.
+const MAX_CANVAS_SIZE: u32 = 8192;
+
+const FOOTPRINT_COLOR: [u8; 4] = [255, 255, 255, 160];
+const SELECTED_COLOR: [u8; 4] = [255, 217, 51, 255];
+
+/// An asset standing on the town grid with the top corner of its footprint at tile (`col`, `row`).
+pub struct Placed {
+    pub scene: Scene,
+    pub col: i32,
+    pub row: i32,
+}
+
+impl Placed {
+    pub fn footprint(&self) -> Footprint {
+        let building = self.scene.building.clone().unwrap_or_default();
+        Footprint {
+            col: self.col,
+            row: self.row,
+            width: building.width as i32,
+            depth: building.depth as i32,
+        }
+    }
+
+    /// Where the asset origin lands in town space.
+    fn origin(&self) -> (f32, f32) {
+        let (x, y) = building::tile_position(self.col as f32, self.row as f32);
+        let (anchor_x, anchor_y) = self.scene.building.clone().unwrap_or_default().anchor();
+        (x + anchor_x, y + anchor_y)
+    }
+}
+
+/// Several assets placed next to each other on an isometric tile grid, using each one's building
+/// XML for its footprint and anchor.
+pub struct Town {
+    pub buildings: Vec<Placed>,
+    pub selected: Option<usize>,
+    /// Building being dragged and the tile within its footprint it was grabbed by.
+    dragging: Option<(usize, (f32, f32))>,
+    pub scale: Vec2<f32>,
+    /// Town space position of the view centre, negated, like [`Scene::offset_x`].
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// Holds every building at 1:1, resized to their bounds as buildings come and go, with the
+    /// canvas position of the town origin.
+    canvas: Option<(Canvas, (f32, f32))>,
+    filter_mode: FilterMode,
+}
+
+impl Town {
+    /// A town of one building.
+    pub fn new(scene: Scene) -> Town {
+        let mut town = Town {
+            buildings: vec![],
+            selected: None,
+            dragging: None,
+            scale: Vec2::one(),
+            offset_x: 0.0,
+            offset_y: 0.0,
+            canvas: None,
+            filter_mode: FilterMode::Linear,
+        };
+        town.add(scene);
+        town
+    }
+
+    /// Places `scene` one tile to the right of every building, and selects it.
+    pub fn add(&mut self, scene: Scene) {
+        let col = self
+            .buildings
+            .iter()
+            .map(|placed| {
+                let footprint = placed.footprint();
+                footprint.col + footprint.width + 1
+            })
+            .max()
+            .unwrap_or(0);
+        self.buildings.push(Placed { scene, col, row: 0 });
+        self.selected = Some(self.buildings.len() - 1);
+    }
+
+    pub fn remove_selected(&mut self) {
+        if let Some(index) = self.selected.take() {
+            self.buildings.remove(index);
+            self.dragging = None;
+        }
+    }
+
+    /// Indices of the buildings back to front.
+    pub fn draw_order(&self) -> Vec<usize> {
+        let footprints = self
+            .buildings
+            .iter()
+            .map(Placed::footprint)
+            .collect::<Vec<Footprint>>();
+        building::draw_order(&footprints)
+    }
+
+    /// Town space bounds of everything the buildings draw.
+    fn bounds(&self) -> Option<Rect> {
+        self.buildings
+            .iter()
+            .filter_map(|placed| {
+                let bounds = placed.scene.bounds?;
+                let (x, y) = placed.origin();
+                Some(Rect::new(
+                    bounds.x + x,
+                    bounds.y + y,
+                    bounds.width,
+                    bounds.height,
+                ))
+            })
+            .reduce(|bounds, building| bounds.union(&building))
+    }
+
+    /// Centres the town and zooms so every building fills a `width` by `height` view.
+    pub fn fit(&mut self, width: f32, height: f32) {
+        let Some(bounds) = self.bounds() else {
+            return;
+        };
+
+        let (center_x, center_y) = bounds.center();
+        self.offset_x = -center_x;
+        self.offset_y = -center_y;
+
+        let scale = (width / bounds.width).min(height / bounds.height) * FIT_MARGIN;
+        self.scale = Vec2::broadcast(scale.clamp(MIN_SCALE, MAX_SCALE));
+    }
+
+    /// Multiplies the zoom by `factor`, keeping the point under `anchor` in place, like
+    /// [`Scene::zoom_at`].
+    pub fn zoom_at(&mut self, factor: f32, anchor: Vec2<f32>) {
+        let scale = (self.scale.x * factor).clamp(MIN_SCALE, MAX_SCALE);
+        self.offset_x += anchor.x / scale - anchor.x / self.scale.x;
+        self.offset_y += anchor.y / scale - anchor.y / self.scale.y;
+        self.scale = Vec2::broadcast(scale);
+    }
+
+    /// Position in town space under `screen`.
+    pub fn screen_to_town(&self, screen: Vec2<f32>, view_size: Vec2<f32>) -> (f32, f32) {
+        let point = (screen - view_size / 2.0) / self.scale;
+        (point.x - self.offset_x, point.y - self.offset_y)
+    }
+
+    fn town_to_screen(&self, (x, y): (f32, f32), view_size: Vec2<f32>) -> Vec2<f32> {
+        view_size / 2.0 + Vec2::new(x + self.offset_x, y + self.offset_y) * self.scale
+    }
+
+    pub fn set_filter_mode(&mut self, ctx: &mut Context, filter_mode: FilterMode) {
+        self.filter_mode = filter_mode;
+        if let Some((canvas, _)) = &mut self.canvas {
+            canvas.set_filter_mode(ctx, filter_mode);
+        }
+        for placed in &mut self.buildings {
+            placed.scene.set_filter_mode(ctx, filter_mode);
+        }
+    }
+
+    /// Draws what [`Town::draw`] rendered, zoomed and panned.
+    pub fn draw_canvas(&self, ctx: &mut Context, view_size: Vec2<f32>) {
+        let Some((canvas, (origin_x, origin_y))) = &self.canvas else {
+            return;
+        };
+        canvas.draw(
+            ctx,
+            DrawParams::default()
+                .position(self.town_to_screen((-origin_x, -origin_y), view_size))
+                .scale(self.scale),
+        );
+    }
+
+    /// Frontmost building whose footprint or image is at `point` in town space.
+    pub fn building_at(&self, point: (f32, f32)) -> Option<usize> {
+        let tile = building::tile_at(point);
+        self.draw_order().into_iter().rev().find(|&index| {
+            let placed = &self.buildings[index];
+            let (x, y) = placed.origin();
+            placed.footprint().contains(tile)
+                || placed.scene.sprite_at((point.0 - x, point.1 - y)).is_some()
+        })
+    }
+
+    /// Selects the building at `point` and starts dragging it, returning whether there was one.
+    pub fn grab(&mut self, point: (f32, f32)) -> bool {
+        self.selected = self.building_at(point);
+        self.dragging = self.selected.map(|index| {
+            let (col, row) = building::tile_at(point);
+            let placed = &self.buildings[index];
+            (index, (col - placed.col as f32, row - placed.row as f32))
+        });
+        self.dragging.is_some()
+    }
+
+    /// Moves the dragged building so the tile it was grabbed by is under `point`.
+    pub fn drag_to(&mut self, point: (f32, f32)) -> bool {
+        let Some((index, (grab_col, grab_row))) = self.dragging else {
+            return false;
+        };
+        let (col, row) = building::tile_at(point);
+        let placed = &mut self.buildings[index];
+        placed.col = (col - grab_col).round() as i32;
+        placed.row = (row - grab_row).round() as i32;
+        true
+    }
+
+    pub fn release(&mut self) {
+        self.dragging = None;
+    }
+
+    /// Renders every building's current tick back to front into a canvas sized to fit them all
+    /// and advances each by `seconds` at its own frame rate, tweening between steps if `tween`.
+    pub fn draw(&mut self, ctx: &mut Context, seconds: f64, tween: bool) -> tetra::Result {
+        let (width, height, origin) = self.bounds().map_or((1, 1, (0.0, 0.0)), |bounds| {
+            canvas_for_bounds(&Rect::new(
+                bounds.x - CANVAS_MARGIN,
+                bounds.y - CANVAS_MARGIN,
+                bounds.width + 2.0 * CANVAS_MARGIN,
+                bounds.height + 2.0 * CANVAS_MARGIN,
+            ))
+        });
+        let (width, height) = (width.min(MAX_CANVAS_SIZE), height.min(MAX_CANVAS_SIZE));
+        let resized = match &self.canvas {
+            Some((canvas, _)) => canvas.size() != (width as i32, height as i32),
+            None => true,
+        };
+        if resized {
+            let mut canvas = Canvas::new(ctx, width as i32, height as i32)?;
+            canvas.set_filter_mode(ctx, self.filter_mode);
+            self.canvas = Some((canvas, origin));
+        } else if let Some((_, canvas_origin)) = &mut self.canvas {
+            *canvas_origin = origin;
+        }
+        let Some((canvas, _)) = &self.canvas else {
+            return Ok(());
+        };
+
+        graphics::set_canvas(ctx, canvas);
+        graphics::clear(ctx, Color::rgba(0.0, 0.0, 0.0, 0.0));
+
+        for index in self.draw_order() {
+            let placed = &mut self.buildings[index];
+            let (x, y) = placed.origin();
+            // Scenes draw their origin at CANVAS_HALF plus their offset
+            placed.scene.offset_x = origin.0 + x - CANVAS_HALF;
+            placed.scene.offset_y = origin.1 + y - CANVAS_HALF;
+            let steps = (placed.scene.frame_rate * seconds) as f32;
+            placed.scene.draw_tick(ctx, 0, steps, tween);
+        }
+
+        graphics::reset_canvas(ctx);
+        Ok(())
+    }
+
+    /// Outlines each footprint, the selected one highlighted, over the tile grid if enabled.
+    pub fn draw_footprints(
+        &self,
+        ctx: &mut Context,
+        overlays: &Overlays,
+        view_size: Vec2<f32>,
+    ) -> tetra::Result {
+        let (left, top) = self.screen_to_town(Vec2::zero(), view_size);
+        let (right, bottom) = self.screen_to_town(view_size, view_size);
+        let grid = Overlays {
+            tile_grid: overlays.tile_grid,
+            ..Overlays::default()
+        };
+        let mut lines = grid.lines(&[], &Rect::new(left, top, right - left, bottom - top));
+
+        for (index, placed) in self.buildings.iter().enumerate() {
+            let color = if self.selected == Some(index) {
+                SELECTED_COLOR
+            } else {
+                FOOTPRINT_COLOR
+            };
+            let corners = placed.footprint().corners();
+            lines.extend((0..4).map(|corner| Line {
+                from: corners[corner],
+                to: corners[(corner + 1) % 4],
+                color,
+            }));
+        }
+
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder = GeometryBuilder::new();
+        for line in lines {
+            let [r, g, b, a] = line.color;
+            builder.set_color(Color::rgba8(r, g, b, a));
+            builder.polyline(
+                1.0,
+                &[
+                    self.town_to_screen(line.from, view_size),
+                    self.town_to_screen(line.to, view_size),
+                ],
+            )?;
+        }
+        builder.build_mesh(ctx)?.draw(ctx, DrawParams::default());
+
+        Ok(())
+    }
+
+    /// Number of buildings and where the selected one stands.
+    pub fn status(&self) -> String {
+        let selected = match self.selected {
+            Some(index) => {
+                let placed = &self.buildings[index];
+                let footprint = placed.footprint();
+                let name = std::path::Path::new(&placed.scene.path)
+                    .file_name()
+                    .map_or(placed.scene.path.clone(), |name| {
+                        name.to_string_lossy().to_string()
+                    });
+                format!(
+                    "{} at ({}, {}), {}x{} tiles{}",
+                    name,
+                    placed.col,
+                    placed.row,
+                    footprint.width,
+                    footprint.depth,
+                    if placed.scene.building.is_none() {
+                        " (no building XML)"
+                    } else {
+                        ""
+                    }
+                )
+            }
+            None => String::from("none"),
+        };
+        format!(
+            "Town: {} buildings, selected: {}\nDrop a BSV3 to add it, drag to move, Delete to remove, G to leave",
+            self.buildings.len(),
+            selected
+        )
+    }
+}
@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Ticks between two polls of the watched files.
+const POLL_INTERVAL: usize = 12;
+
+/// Polls modification times of a set of files.
+///
+/// A change is only reported once the times have been stable for a whole poll interval so files
+/// still being written by an exporter aren't picked up half way.
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    ticks: usize,
+    pending: bool,
+}
+
+impl FileWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> FileWatcher {
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let modified = modified(&path);
+                (path, modified)
+            })
+            .collect();
+
+        FileWatcher {
+            files,
+            ticks: 0,
+            pending: false,
+        }
+    }
+
+    /// Call once per tick, returns true when a watched file changed and has settled.
+    pub fn changed(&mut self) -> bool {
+        self.changed_with(modified)
+    }
+
+    /// [`FileWatcher::changed`] with the modification times from `modified`.
+    fn changed_with(&mut self, modified: impl Fn(&Path) -> Option<SystemTime>) -> bool {
+        self.ticks += 1;
+        if self.ticks < POLL_INTERVAL {
+            return false;
+        }
+        self.ticks = 0;
+
+        let mut changed = false;
+        for (path, last) in self.files.iter_mut() {
+            let current = modified(path);
+            if current != *last {
+                *last = current;
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.pending = true;
+            return false;
+        }
+
+        std::mem::take(&mut self.pending)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    /// Watches one file whose modification time is whatever `time` holds, in seconds.
+    fn watch(time: &Cell<u64>) -> (FileWatcher, impl Fn(&Path) -> Option<SystemTime> + '_) {
+        let watcher = FileWatcher {
+            files: vec![(
+                PathBuf::from("asset.bsv3"),
+                Some(SystemTime::UNIX_EPOCH + Duration::from_secs(time.get())),
+            )],
+            ticks: 0,
+            pending: false,
+        };
+        let modified = |_: &Path| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(time.get()));
+        (watcher, modified)
+    }
+
+    /// Ticks of a whole poll interval, returning whether the last one reported a change.
+    fn poll(watcher: &mut FileWatcher, modified: &impl Fn(&Path) -> Option<SystemTime>) -> bool {
+        (0..POLL_INTERVAL)
+            .map(|_| watcher.changed_with(modified))
+            .fold(false, |_, changed| changed)
+    }
+
+    #[test]
+    fn unchanged_files_are_never_reported() {
+        let time = Cell::new(1);
+        let (mut watcher, modified) = watch(&time);
+
+        for _ in 0..5 {
+            assert!(!poll(&mut watcher, &modified));
+        }
+    }
+
+    #[test]
+    fn changes_are_reported_once_settled() {
+        let time = Cell::new(1);
+        let (mut watcher, modified) = watch(&time);
+
+        time.set(2);
+        // Only polled once per interval
+        for _ in 0..POLL_INTERVAL - 1 {
+            assert!(!watcher.changed_with(&modified));
+        }
+        // Seen, but it may still be being written
+        assert!(!watcher.changed_with(&modified));
+        // Unchanged for a whole interval
+        assert!(poll(&mut watcher, &modified));
+        // And only reported once
+        assert!(!poll(&mut watcher, &modified));
+    }
+
+    #[test]
+    fn waits_while_the_file_keeps_changing() {
+        let time = Cell::new(1);
+        let (mut watcher, modified) = watch(&time);
+
+        for written in 2..6 {
+            time.set(written);
+            assert!(!poll(&mut watcher, &modified));
+        }
+        assert!(poll(&mut watcher, &modified));
+    }
+
+    #[test]
+    fn deleted_files_count_as_changed() {
+        let time = Cell::new(1);
+        let (mut watcher, _) = watch(&time);
+        let deleted = |_: &Path| None;
+
+        assert!(!poll(&mut watcher, &deleted));
+        assert!(poll(&mut watcher, &deleted));
+    }
+}